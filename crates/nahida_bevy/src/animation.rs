@@ -0,0 +1,151 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::prelude::*;
+use nahida_core::story::{Animation, AnimationType, Keyframe};
+
+use crate::{
+  material::stage::StageMaterial, sync_transform_with_location, Location, OriginalImage, WindowSize,
+};
+
+/// how far the image shakes, relative to the window width
+const SHAKE_AMPLITUDE: f32 = 0.01;
+/// how many times the image shakes during the animation
+const SHAKE_TIMES: f32 = 8.0;
+
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct AnimationSet;
+
+pub struct NahidaAnimationPlugin;
+
+impl Plugin for NahidaAnimationPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_system(
+      animate_image
+        .in_set(AnimationSet)
+        .after(sync_transform_with_location),
+    );
+  }
+}
+
+/// animation playing on a background or a figure
+#[derive(Component)]
+pub struct ImageAnimation {
+  animation: Animation,
+  elapsed: Duration,
+}
+
+impl ImageAnimation {
+  pub fn new(animation: Animation) -> Self {
+    Self {
+      animation,
+      elapsed: Duration::ZERO,
+    }
+  }
+}
+
+/// horizontal offset of a shaking image, fading out towards the end
+pub(crate) fn shake_offset(progress: f32, window_width: f32) -> f32 {
+  (progress * TAU * SHAKE_TIMES).sin() * (1.0 - progress) * SHAKE_AMPLITUDE * window_width
+}
+
+fn lerp_transform(from: &Transform, to: &Transform, t: f32) -> Transform {
+  Transform {
+    translation: from.translation.lerp(to.translation, t),
+    rotation: from.rotation.slerp(to.rotation, t),
+    scale: from.scale.lerp(to.scale, t),
+  }
+}
+
+/// runs after the transform is synced with the location, so that animations
+/// apply on top of where the image rests
+#[allow(clippy::type_complexity)]
+fn animate_image(
+  mut query: Query<(
+    &OriginalImage,
+    &Location,
+    &mut ImageAnimation,
+    &mut Transform,
+    Option<&Handle<StageMaterial>>,
+  )>,
+  images: Res<Assets<Image>>,
+  mut materials: ResMut<Assets<StageMaterial>>,
+  window_size: Res<WindowSize>,
+  time: Res<Time>,
+) {
+  for (texture, location, mut animation, mut transform, material) in query.iter_mut() {
+    animation.elapsed += time.delta();
+
+    let Some(image) = images.get(&texture.0) else {
+      continue;
+    };
+    let window_size = (window_size.0, window_size.1);
+    let image_size = image.size();
+    let image_aspect = image_size.x / image_size.y;
+    let compute_transform = |target: &nahida_core::Location| {
+      let location = Location {
+        location: target.clone(),
+        z_index: location.z_index,
+      };
+      location.compute_transform(window_size, image_aspect)
+    };
+
+    let (mut tint, mut opacity, mut blur) = (Vec4::ONE, 1.0, 0.0);
+    let progress = animation.animation.progress(animation.elapsed);
+
+    match (&animation.animation.ty, progress) {
+      (_, None) => {}
+      (AnimationType::To { location }, Some(progress)) => {
+        *transform = lerp_transform(&transform, &compute_transform(location), progress);
+      }
+      (AnimationType::Keyframes(keyframes), Some(progress)) => {
+        if let Some((index, progress)) = Keyframe::locate(keyframes, progress) {
+          // start from where the previous keyframes left the image
+          let from = keyframes[..index]
+            .iter()
+            .rev()
+            .find_map(|x| x.location.as_ref())
+            .map_or(*transform, compute_transform);
+          let to = keyframes[index]
+            .location
+            .as_ref()
+            .map_or(from, compute_transform);
+          *transform = lerp_transform(&from, &to, progress);
+        }
+      }
+      (AnimationType::Shake, Some(progress)) => {
+        transform.translation.x += shake_offset(progress, window_size.0);
+      }
+      (AnimationType::Rotate { degrees }, Some(progress)) => {
+        // clockwise on the screen
+        transform.rotation = Quat::from_rotation_z(-(degrees * progress).to_radians());
+      }
+      (AnimationType::Scale { factor }, Some(progress)) => {
+        let scale = 1.0 + (factor - 1.0) * progress;
+        transform.scale.x *= scale;
+        transform.scale.y *= scale;
+      }
+      (AnimationType::Opacity { opacity: target }, Some(progress)) => {
+        opacity = 1.0 + (target - 1.0) * progress;
+      }
+      (AnimationType::Blur { radius }, Some(progress)) => {
+        blur = radius * progress;
+      }
+      (AnimationType::Tint { color }, Some(progress)) => {
+        let color = nahida_core::Color::WHITE.lerp(color, progress);
+        tint = Vec4::from(Color::rgba(color.r, color.g, color.b, color.a).as_linear_rgba_f32());
+      }
+    }
+
+    if let Some(handle) = material {
+      // avoid rebuilding the bind group when nothing changes
+      let changed = |x: &StageMaterial| (x.tint, x.opacity, x.blur) != (tint, opacity, blur);
+      if materials.get(handle).is_some_and(changed) {
+        if let Some(material) = materials.get_mut(handle) {
+          material.tint = tint;
+          material.opacity = opacity;
+          material.blur = blur;
+        }
+      }
+    }
+  }
+}
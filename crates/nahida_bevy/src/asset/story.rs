@@ -4,7 +4,7 @@ use bevy::{
   reflect::TypeUuid,
 };
 use nahida_core::story::Story;
-use nahida_parser::parse_story;
+use nahida_parser::{parse_story_with_diagnostics, Severity};
 
 pub struct StoryAssetPlugin;
 
@@ -33,7 +33,24 @@ impl AssetLoader for StoryAssetLoader {
   ) -> bevy::utils::BoxedFuture<'a, Result<(), bevy::asset::Error>> {
     Box::pin(async move {
       let text = String::from_utf8_lossy(bytes);
-      let story = parse_story(&text)?;
+      let file_name = load_context.path().display().to_string();
+      let output = parse_story_with_diagnostics(&text);
+
+      for diagnostic in &output.diagnostics {
+        let message = diagnostic.render(&file_name, &text);
+        match diagnostic.severity {
+          Severity::Warning => warn!("{message}"),
+          Severity::Error => error!("{message}"),
+        }
+      }
+
+      if output.has_errors() {
+        Err(bevy::asset::Error::msg(format!(
+          "failed to parse {file_name}"
+        )))?
+      }
+
+      let story = output.story;
       load_context.set_default_asset(LoadedAsset::new(StoryAsset { story }));
       Ok(())
     })
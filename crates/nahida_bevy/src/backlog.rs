@@ -0,0 +1,336 @@
+use std::{collections::VecDeque, path::PathBuf};
+
+use bevy::{
+  input::mouse::{MouseScrollUnit, MouseWheel},
+  prelude::*,
+  ui::FocusPolicy,
+};
+use nahida_core::text::RichText;
+
+use crate::{
+  settings::{AudioChannel, NahidaSettings},
+  state::{
+    loading::{NahidaFonts, NahidaResources},
+    playing::SaveScreen,
+    NahidaState,
+  },
+  text::rich_text_sections,
+};
+
+pub struct NahidaBacklogPlugin;
+
+impl Plugin for NahidaBacklogPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Backlog>()
+      .add_event::<TextShown>()
+      .add_system(record_backlog)
+      .add_systems(
+        (toggle_backlog, scroll_backlog, replay_voice).in_set(OnUpdate(NahidaState::Playing)),
+      )
+      .add_system(close_backlog.in_schedule(OnExit(NahidaState::Playing)));
+  }
+}
+
+/// a line of dialogue that has been shown
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacklogEntry {
+  pub name: Option<String>,
+  pub text: RichText,
+  /// absolute path of the voice
+  pub voice: Option<PathBuf>,
+}
+
+/// sent when a text is shown to the player
+pub struct TextShown(pub BacklogEntry);
+
+/// history of the dialogue, the oldest lines are dropped when it is full
+#[derive(Resource, Debug)]
+pub struct Backlog {
+  entries: VecDeque<BacklogEntry>,
+  capacity: usize,
+}
+
+impl Default for Backlog {
+  fn default() -> Self {
+    Self::new(500)
+  }
+}
+
+impl Backlog {
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      entries: VecDeque::new(),
+      capacity,
+    }
+  }
+
+  pub fn push(&mut self, entry: BacklogEntry) {
+    if self.entries.len() >= self.capacity {
+      self.entries.pop_front();
+    }
+    self.entries.push_back(entry);
+  }
+
+  pub fn entries(&self) -> impl Iterator<Item = &BacklogEntry> {
+    self.entries.iter()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+}
+
+fn record_backlog(mut events: EventReader<TextShown>, mut backlog: ResMut<Backlog>) {
+  for TextShown(entry) in events.iter() {
+    backlog.push(entry.clone());
+  }
+}
+
+/// the backlog screen, covering the stage while it is open
+#[derive(Component)]
+pub struct BacklogScreen;
+
+/// lines in the screen, scrolled up by `position` pixels
+#[derive(Component, Default)]
+struct BacklogList {
+  position: f32,
+}
+
+#[derive(Component)]
+struct VoiceButton(PathBuf);
+
+#[allow(clippy::too_many_arguments)]
+fn toggle_backlog(
+  mut command: Commands,
+  keys: Res<Input<KeyCode>>,
+  buttons: Res<Input<MouseButton>>,
+  mut wheel: EventReader<MouseWheel>,
+  screens: Query<Entity, With<BacklogScreen>>,
+  saves: Query<(), With<SaveScreen>>,
+  backlog: Res<Backlog>,
+  fonts: Res<NahidaFonts>,
+) {
+  let scrolled_up = wheel.iter().any(|event| event.y > 0.0);
+
+  if let Ok(screen) = screens.get_single() {
+    if keys.any_just_pressed([KeyCode::L, KeyCode::Escape])
+      || buttons.just_pressed(MouseButton::Right)
+    {
+      command.entity(screen).despawn_recursive();
+    }
+    return;
+  }
+
+  if backlog.is_empty() || !saves.is_empty() || !(keys.just_pressed(KeyCode::L) || scrolled_up) {
+    return;
+  }
+
+  let style = TextStyle {
+    font: fonts.hanyi.clone(),
+    font_size: 24.0,
+    color: Color::WHITE,
+  };
+
+  command
+    .spawn((
+      BacklogScreen,
+      NodeBundle {
+        style: Style {
+          position_type: PositionType::Absolute,
+          size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+          flex_direction: FlexDirection::Column,
+          justify_content: JustifyContent::FlexEnd,
+          overflow: Overflow::Hidden,
+          padding: UiRect::all(Val::Px(40.0)),
+          ..Default::default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+        focus_policy: FocusPolicy::Block,
+        z_index: ZIndex::Global(10),
+        ..Default::default()
+      },
+    ))
+    .with_children(|screen| {
+      screen
+        .spawn((
+          BacklogList::default(),
+          NodeBundle {
+            style: Style {
+              flex_direction: FlexDirection::Column,
+              flex_shrink: 0.0,
+              ..Default::default()
+            },
+            ..Default::default()
+          },
+        ))
+        .with_children(|list| {
+          for entry in backlog.entries() {
+            spawn_entry(list, entry, &style);
+          }
+        });
+    });
+}
+
+fn close_backlog(mut command: Commands, screens: Query<Entity, With<BacklogScreen>>) {
+  for screen in screens.iter() {
+    command.entity(screen).despawn_recursive();
+  }
+}
+
+fn spawn_entry(list: &mut ChildBuilder, entry: &BacklogEntry, style: &TextStyle) {
+  list
+    .spawn(NodeBundle {
+      style: Style {
+        flex_direction: FlexDirection::Row,
+        align_items: AlignItems::Center,
+        margin: UiRect::bottom(Val::Px(16.0)),
+        ..Default::default()
+      },
+      ..Default::default()
+    })
+    .with_children(|row| {
+      if let Some(voice) = &entry.voice {
+        row
+          .spawn((
+            VoiceButton(voice.clone()),
+            ButtonBundle {
+              style: Style {
+                size: Size::new(Val::Px(40.0), Val::Px(40.0)),
+                margin: UiRect::right(Val::Px(16.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+              },
+              background_color: Color::rgba(1.0, 1.0, 1.0, 0.2).into(),
+              ..Default::default()
+            },
+          ))
+          .with_children(|button| {
+            button.spawn(TextBundle::from_section("▶", style.clone()));
+          });
+      }
+
+      let mut sections = Vec::new();
+      if let Some(name) = &entry.name {
+        sections.push(TextSection::new(
+          format!("{name}\n"),
+          TextStyle {
+            color: Color::GOLD,
+            ..style.clone()
+          },
+        ));
+      }
+      sections.extend(rich_text_sections(&entry.text, style));
+
+      row.spawn(TextBundle::from_sections(sections).with_style(Style {
+        max_size: Size::width(Val::Percent(90.0)),
+        ..Default::default()
+      }));
+    });
+}
+
+fn scroll_backlog(
+  mut wheel: EventReader<MouseWheel>,
+  mut lists: Query<(&mut BacklogList, &mut Style, &Parent, &Node)>,
+  nodes: Query<&Node>,
+) {
+  for event in wheel.iter() {
+    let delta = match event.unit {
+      MouseScrollUnit::Line => event.y * 24.0,
+      MouseScrollUnit::Pixel => event.y,
+    };
+
+    for (mut list, mut style, parent, node) in lists.iter_mut() {
+      let Ok(screen) = nodes.get(parent.get()) else {
+        continue;
+      };
+      let max_position = (node.size().y - screen.size().y).max(0.0);
+
+      list.position = (list.position + delta).clamp(0.0, max_position);
+      style.position.top = Val::Px(list.position);
+    }
+  }
+}
+
+fn replay_voice(
+  buttons: Query<(&Interaction, &VoiceButton), Changed<Interaction>>,
+  resources: Res<NahidaResources>,
+  audio: Res<Audio>,
+  settings: Res<NahidaSettings>,
+) {
+  let volume = settings.volume(AudioChannel::Voice);
+
+  for (interaction, VoiceButton(voice)) in buttons.iter() {
+    if *interaction != Interaction::Clicked {
+      continue;
+    }
+
+    match resources.audio(voice) {
+      Some(source) => {
+        audio.play_with_settings(source.clone(), PlaybackSettings::ONCE.with_volume(volume));
+      }
+      None => error!("Audio Not Found: {voice:?}"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::prelude::*;
+
+  use crate::state::NahidaState;
+
+  use super::{close_backlog, record_backlog, Backlog, BacklogEntry, BacklogScreen, TextShown};
+
+  fn entry(text: &str) -> BacklogEntry {
+    BacklogEntry {
+      name: Some("纳西妲".into()),
+      text: text.into(),
+      voice: None,
+    }
+  }
+
+  #[test]
+  fn test_record_backlog() {
+    let mut app = App::new();
+    app
+      .add_plugins(MinimalPlugins)
+      .insert_resource(Backlog::new(2))
+      .add_event::<TextShown>()
+      .add_system(record_backlog);
+
+    for text in ["a", "b", "c"] {
+      app.world.send_event(TextShown(entry(text)));
+    }
+    app.update();
+
+    let backlog = app.world.resource::<Backlog>();
+    assert_eq!(
+      backlog.entries().cloned().collect::<Vec<_>>(),
+      vec![entry("b"), entry("c")]
+    );
+  }
+
+  #[test]
+  fn test_close_backlog() {
+    let mut app = App::new();
+    app
+      .add_plugins(MinimalPlugins)
+      .add_state::<NahidaState>()
+      .insert_resource(NextState(Some(NahidaState::Playing)))
+      .add_system(close_backlog.in_schedule(OnExit(NahidaState::Playing)));
+    app.update();
+
+    let screen = app.world.spawn(BacklogScreen).id();
+    app
+      .world
+      .insert_resource(NextState(Some(NahidaState::Menu)));
+    app.update();
+    assert!(app.world.get_entity(screen).is_none());
+  }
+}
@@ -1,16 +1,87 @@
+use animation::NahidaAnimationPlugin;
 use asset::NahidaAssetPlugin;
+use backlog::NahidaBacklogPlugin;
+use std::path::PathBuf;
+
 use bevy::{prelude::*, window::WindowResized};
 
 use material::NahidaMaterialPlugin;
+use mode::NahidaModePlugin;
+use player::NahidaPlayerPlugin;
+use save::NahidaSavePlugin;
+use settings::NahidaSettingsPlugin;
+use stage::NahidaStagePlugin;
 use state::NahidaStatePlugin;
-
+use transition::NahidaTransitionPlugin;
+
+pub use animation::ImageAnimation;
+pub use backlog::{Backlog, BacklogEntry, TextShown};
+pub use material::stage::StageMaterial;
+pub use mode::PlayMode;
+pub use player::{NahidaPlayer, NahidaPlayerConfig};
+pub use save::{LoadRequest, SaveData, SaveRequest, SaveSlot, SaveStorage};
+pub use settings::{AudioChannel, NahidaSettings, SettingsStorage};
+pub use state::menu::{MenuAction, NahidaMenuConfig, OpenSettings};
+pub use transition::ImageTransition;
+
+mod animation;
 mod asset;
+mod backlog;
 mod material;
+mod mode;
+mod player;
+mod save;
+mod settings;
+mod stage;
 mod state;
+mod text;
+mod transition;
 
 #[derive(Resource)]
 pub struct NahidaEntryPoint(pub String);
 
+/// name of the game, set by the project before adding the plugin,
+/// the user data is kept in a folder of this name
+#[derive(Resource)]
+pub struct NahidaAppName(pub String);
+
+/// folder of the app in the user data directory of the platform
+fn data_dir(app: &str) -> Option<PathBuf> {
+  let var = |key| {
+    std::env::var_os(key)
+      .filter(|x| !x.is_empty())
+      .map(PathBuf::from)
+  };
+
+  let dir = if cfg!(target_os = "windows") {
+    var("APPDATA")
+  } else if cfg!(target_os = "macos") {
+    var("HOME").map(|x| x.join("Library/Application Support"))
+  } else {
+    var("XDG_DATA_HOME").or_else(|| var("HOME").map(|x| x.join(".local/share")))
+  };
+  dir.map(|x| x.join(app))
+}
+
+/// the storage made by `new` for the app named in the world,
+/// the `data` is not kept without a place to put it
+fn storage_from_world<T>(
+  world: &World,
+  data: &str,
+  new: impl FnOnce(&str) -> Option<T>,
+) -> Option<T> {
+  let Some(NahidaAppName(name)) = world.get_resource() else {
+    error!("No app name is set, the {data} will not be kept");
+    return None;
+  };
+
+  let storage = new(name);
+  if storage.is_none() {
+    error!("No user data directory is found, the {data} will not be kept");
+  }
+  storage
+}
+
 pub struct NahidaPlugin;
 
 impl Plugin for NahidaPlugin {
@@ -20,6 +91,14 @@ impl Plugin for NahidaPlugin {
       .add_plugin(NahidaStatePlugin)
       .add_plugin(NahidaAssetPlugin)
       .add_plugin(NahidaMaterialPlugin)
+      .add_plugin(NahidaAnimationPlugin)
+      .add_plugin(NahidaTransitionPlugin)
+      .add_plugin(NahidaPlayerPlugin)
+      .add_plugin(NahidaStagePlugin)
+      .add_plugin(NahidaSavePlugin)
+      .add_plugin(NahidaBacklogPlugin)
+      .add_plugin(NahidaModePlugin)
+      .add_plugin(NahidaSettingsPlugin)
       .insert_resource(ClearColor(Color::BLACK))
       .insert_resource(WindowSize(0.0, 0.0))
       .add_startup_system(setup_camera)
@@ -1,13 +1,17 @@
 use bevy::prelude::*;
 
 pub mod menu;
+pub mod stage;
 
 use menu::MenuMaterialPlugin;
+use stage::StageMaterialPlugin;
 
 pub struct NahidaMaterialPlugin;
 
 impl Plugin for NahidaMaterialPlugin {
   fn build(&self, app: &mut App) {
-    app.add_plugin(MenuMaterialPlugin);
+    app
+      .add_plugin(MenuMaterialPlugin)
+      .add_plugin(StageMaterialPlugin);
   }
 }
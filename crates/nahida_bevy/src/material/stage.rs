@@ -0,0 +1,107 @@
+use bevy::{
+  asset::load_internal_asset,
+  prelude::*,
+  reflect::TypeUuid,
+  render::render_resource::{AsBindGroup, ShaderRef},
+  sprite::{Material2d, Material2dPlugin},
+};
+use nahida_core::story::TransitionType;
+
+const STAGE_SHADER_HANDLE: HandleUntyped =
+  HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 3906297625426385067);
+
+pub struct StageMaterialPlugin;
+
+impl Plugin for StageMaterialPlugin {
+  fn build(&self, app: &mut App) {
+    load_internal_asset!(
+      app,
+      STAGE_SHADER_HANDLE,
+      "../shader/stage.wgsl",
+      Shader::from_wgsl
+    );
+
+    app.add_plugin(Material2dPlugin::<StageMaterial>::default());
+  }
+}
+
+/// material of backgrounds and figures, with the effects applied by animations
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "5d7b7a4e-1c43-4c57-9a0e-6c2f3d1b8e21"]
+pub struct StageMaterial {
+  /// linear color multiplied with the image, blended by its alpha
+  #[uniform(0)]
+  pub tint: Vec4,
+  #[uniform(1)]
+  pub opacity: f32,
+  /// blur radius in texels
+  #[uniform(2)]
+  pub blur: f32,
+  #[texture(3)]
+  #[sampler(4)]
+  pub texture: Handle<Image>,
+  /// kind of the transition playing, see `transition_kind`
+  #[uniform(5)]
+  pub transition: u32,
+  /// eased progress of the transition
+  #[uniform(6)]
+  pub progress: f32,
+  /// grayscale image of the rule transition
+  #[texture(7)]
+  #[sampler(8)]
+  pub mask: Option<Handle<Image>>,
+  #[uniform(9)]
+  pub softness: f32,
+}
+
+impl StageMaterial {
+  pub fn new(texture: Handle<Image>) -> Self {
+    Self {
+      tint: Vec4::ONE,
+      opacity: 1.0,
+      blur: 0.0,
+      texture,
+      transition: 0,
+      progress: 0.0,
+      mask: None,
+      softness: 0.0,
+    }
+  }
+}
+
+/// how `transition_mask` in the shader identifies a transition,
+/// `0` for the ones leaving the image untouched
+pub fn transition_kind(ty: &TransitionType) -> u32 {
+  match ty {
+    TransitionType::FadeIn => 1,
+    TransitionType::FadeOut => 2,
+    TransitionType::ConicIn => 3,
+    TransitionType::ConicOut => 4,
+    TransitionType::BlindsIn => 5,
+    TransitionType::BlindsOut => 6,
+    TransitionType::Rule { .. } => 7,
+    TransitionType::Shake => 0,
+  }
+}
+
+impl Material2d for StageMaterial {
+  fn fragment_shader() -> ShaderRef {
+    STAGE_SHADER_HANDLE.typed().into()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+  #[test]
+  fn test_shader() {
+    let source = include_str!("../shader/stage.wgsl");
+    let module = naga::front::wgsl::parse_str(source)
+      .unwrap_or_else(|e| panic!("{}", e.emit_to_string(source)));
+
+    Validator::new(ValidationFlags::all(), Capabilities::empty())
+      .validate(&module)
+      .unwrap();
+  }
+}
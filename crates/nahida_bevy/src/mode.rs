@@ -0,0 +1,368 @@
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use bevy::{
+  audio::{Decodable, Source},
+  prelude::*,
+};
+use nahida_core::{path::join_absolute_path, story::StoryAction};
+
+use crate::{
+  asset::story::StoryAsset,
+  player::{AdvanceRequest, AdvanceSet, NahidaPlayer, StepPlayed, StoryAssets},
+  state::{loading::NahidaResources, playing::Overlays, NahidaState},
+  text::Typewriter,
+  ImageTransition,
+};
+
+pub struct NahidaModePlugin;
+
+impl Plugin for NahidaModePlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<PlayMode>()
+      .init_resource::<AutoTimer>()
+      .init_resource::<VoiceDurations>()
+      .add_system(reset_auto_timer)
+      .add_systems(
+        (
+          toggle_mode,
+          auto_advance.after(reset_auto_timer).before(AdvanceSet),
+          skip_advance.after(toggle_mode).before(AdvanceSet),
+        )
+          .in_set(OnUpdate(NahidaState::Playing)),
+      );
+  }
+}
+
+/// how the steps go on without clicking
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayMode {
+  #[default]
+  Normal,
+  /// go on after the text is read and the voice ends
+  Auto,
+  /// go through the steps seen before, with transitions skipped
+  Skip,
+}
+
+/// time spent on the current step in auto mode
+#[derive(Resource, Default)]
+struct AutoTimer {
+  elapsed: Duration,
+  delay: Duration,
+}
+
+/// lengths of the voices, decoded once
+#[derive(Resource, Default)]
+struct VoiceDurations(HashMap<PathBuf, Duration>);
+
+/// `A` toggles auto mode and `S` toggles skip mode
+fn toggle_mode(
+  keys: Res<Input<KeyCode>>,
+  mut mode: ResMut<PlayMode>,
+  overlays: Query<(), Overlays>,
+) {
+  if !overlays.is_empty() {
+    return;
+  }
+
+  let toggle = |mode: PlayMode, target: PlayMode| match mode == target {
+    true => PlayMode::Normal,
+    false => target,
+  };
+
+  if keys.just_pressed(KeyCode::A) {
+    *mode = toggle(*mode, PlayMode::Auto);
+  }
+  if keys.just_pressed(KeyCode::S) {
+    *mode = toggle(*mode, PlayMode::Skip);
+  }
+}
+
+/// length of the audio, decoding it if the format does not tell
+fn audio_duration(source: &AudioSource) -> Duration {
+  let decoder = source.decoder();
+  if let Some(duration) = decoder.total_duration() {
+    return duration;
+  }
+
+  let rate = decoder.channels() as f64 * decoder.sample_rate() as f64;
+  let samples = decoder.count() as f64;
+  Duration::from_secs_f64(samples / rate.max(1.0))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn reset_auto_timer(
+  mut events: EventReader<StepPlayed>,
+  mut timer: ResMut<AutoTimer>,
+  mut durations: ResMut<VoiceDurations>,
+  player: Res<NahidaPlayer>,
+  resources: Res<NahidaResources>,
+  stories: Res<Assets<StoryAsset>>,
+  audios: Res<Assets<AudioSource>>,
+) {
+  if events.iter().count() == 0 {
+    return;
+  }
+
+  let loader = StoryAssets {
+    resources: &resources,
+    assets: &stories,
+  };
+  let (Some(cursor), Some(step)) = (player.player.cursor(), player.player.current_step(&loader))
+  else {
+    return;
+  };
+
+  let mut voice_duration = |url: &PathBuf| {
+    let path = join_absolute_path(&cursor.path, url)?;
+    if let Some(duration) = durations.0.get(&path) {
+      return Some(*duration);
+    }
+
+    let source = audios.get(resources.audio(&path)?)?;
+    let duration = audio_duration(source);
+    durations.0.insert(path, duration);
+    Some(duration)
+  };
+
+  let config = player.player.config();
+  timer.elapsed = Duration::ZERO;
+  timer.delay = step
+    .actions
+    .iter()
+    .filter_map(|action| match action {
+      StoryAction::Text { text, voice, .. } => {
+        let voice = voice.as_ref().and_then(&mut voice_duration);
+        Some(config.auto_delay(text, voice))
+      }
+      _ => None,
+    })
+    .max()
+    .unwrap_or(config.auto_delay);
+}
+
+fn auto_advance(
+  mode: Res<PlayMode>,
+  mut timer: ResMut<AutoTimer>,
+  transitions: Query<&ImageTransition>,
+  typewriters: Query<&Typewriter>,
+  overlays: Query<(), Overlays>,
+  time: Res<Time>,
+  mut advance: EventWriter<AdvanceRequest>,
+) {
+  // the delay starts after the text is typed
+  if *mode != PlayMode::Auto
+    || !overlays.is_empty()
+    || transitions.iter().any(|x| !x.finished())
+    || typewriters.iter().any(|x| !x.finished())
+  {
+    return;
+  }
+
+  timer.elapsed += time.delta();
+  if timer.elapsed >= timer.delay {
+    timer.elapsed = Duration::ZERO;
+    advance.send(AdvanceRequest);
+  }
+}
+
+/// go on until a choice or an unread step
+#[allow(clippy::too_many_arguments)]
+fn skip_advance(
+  mut mode: ResMut<PlayMode>,
+  mut transitions: Query<&mut ImageTransition>,
+  mut typewriters: Query<&mut Typewriter>,
+  overlays: Query<(), Overlays>,
+  player: Res<NahidaPlayer>,
+  resources: Res<NahidaResources>,
+  stories: Res<Assets<StoryAsset>>,
+  mut advance: EventWriter<AdvanceRequest>,
+) {
+  if *mode != PlayMode::Skip || !overlays.is_empty() {
+    return;
+  }
+
+  for mut transition in transitions.iter_mut() {
+    if !transition.finished() {
+      transition.skip();
+    }
+  }
+  for mut typewriter in typewriters.iter_mut() {
+    typewriter.complete();
+  }
+
+  let loader = StoryAssets {
+    resources: &resources,
+    assets: &stories,
+  };
+  match player.player.can_skip(&loader, player.was_seen) {
+    true => advance.send(AdvanceRequest),
+    false => *mode = PlayMode::Normal,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{path::PathBuf, time::Duration};
+
+  use bevy::{input::mouse::MouseWheel, prelude::*};
+  use nahida_core::player::{PlayerConfig, StoryPlayer};
+  use nahida_parser::parse_story;
+
+  use crate::{
+    asset::story::{StoryAsset, StoryAssetPlugin},
+    backlog::{Backlog, NahidaBacklogPlugin},
+    player::{AdvanceRequest, NahidaPlayer, NahidaPlayerConfig, NahidaPlayerPlugin},
+    settings::NahidaSettings,
+    stage::RestoreStage,
+    state::{
+      loading::{NahidaFonts, NahidaResources},
+      NahidaState,
+    },
+    NahidaEntryPoint,
+  };
+
+  use super::{NahidaModePlugin, PlayMode};
+
+  fn setup(config: PlayerConfig) -> App {
+    let entry = PathBuf::from("/story.md");
+    let script = "「一」\n\n「二」\n\n「三」\n\n- 好啊\n- 还是算了\n";
+
+    let mut app = App::new();
+    app
+      .add_plugins(MinimalPlugins)
+      .add_plugin(AssetPlugin::default())
+      .add_plugin(StoryAssetPlugin)
+      .add_asset::<AudioSource>()
+      .init_resource::<Audio>()
+      .init_resource::<Input<KeyCode>>()
+      .init_resource::<Input<MouseButton>>()
+      .init_resource::<NahidaFonts>()
+      .init_resource::<NahidaResources>()
+      .init_resource::<NahidaSettings>()
+      .add_event::<MouseWheel>()
+      .add_event::<RestoreStage>()
+      .add_state::<NahidaState>()
+      .insert_resource(NextState(Some(NahidaState::Playing)))
+      .insert_resource(NahidaEntryPoint(entry.display().to_string()))
+      .insert_resource(NahidaPlayerConfig(config))
+      .add_plugin(NahidaPlayerPlugin)
+      .add_plugin(NahidaBacklogPlugin)
+      .add_plugin(NahidaModePlugin);
+
+    let handle = app
+      .world
+      .resource_mut::<Assets<StoryAsset>>()
+      .add(StoryAsset {
+        story: parse_story(script).unwrap(),
+      });
+    let mut resources = app.world.resource_mut::<NahidaResources>();
+    resources.insert_story(entry, handle);
+    app
+  }
+
+  fn advance(app: &mut App) {
+    app.world.send_event(AdvanceRequest);
+    app.update();
+  }
+
+  fn press(app: &mut App, key: KeyCode) {
+    let mut input = app.world.resource_mut::<Input<KeyCode>>();
+    input.release_all();
+    input.clear();
+    input.press(key);
+    app.update();
+  }
+
+  fn texts(app: &App) -> Vec<String> {
+    let backlog = app.world.resource::<Backlog>();
+    backlog.entries().map(|x| x.text.to_string()).collect()
+  }
+
+  #[test]
+  fn test_skip_mode() {
+    let mut app = setup(PlayerConfig::default());
+    advance(&mut app);
+    advance(&mut app);
+
+    // read the first line again, then skip the lines read
+    app.world.resource_mut::<NahidaPlayer>().player = StoryPlayer::new("/story.md".into());
+    advance(&mut app);
+
+    *app.world.resource_mut::<PlayMode>() = PlayMode::Skip;
+    for _ in 0..5 {
+      app.update();
+    }
+
+    assert_eq!(*app.world.resource::<PlayMode>(), PlayMode::Normal);
+    assert_eq!(
+      texts(&app),
+      vec!["「一」", "「二」", "「一」", "「二」", "「三」"]
+    );
+    let player = &app.world.resource::<NahidaPlayer>().player;
+    assert_eq!(player.cursor().map(|x| x.frames[0].step), Some(2));
+
+    // everything is skipped until the choice when opted in
+    let mut app = setup(PlayerConfig {
+      skip_unread: true,
+      ..Default::default()
+    });
+    advance(&mut app);
+    *app.world.resource_mut::<PlayMode>() = PlayMode::Skip;
+    for _ in 0..5 {
+      app.update();
+    }
+
+    assert_eq!(*app.world.resource::<PlayMode>(), PlayMode::Normal);
+    assert_eq!(texts(&app), vec!["「一」", "「二」", "「三」"]);
+    let player = &app.world.resource::<NahidaPlayer>().player;
+    assert_eq!(player.cursor().map(|x| x.frames[0].step), Some(3));
+  }
+
+  #[test]
+  fn test_toggle_mode() {
+    let mut app = setup(PlayerConfig::default());
+
+    // nothing is read yet, so skip mode stops right away
+    press(&mut app, KeyCode::S);
+    assert_eq!(*app.world.resource::<PlayMode>(), PlayMode::Normal);
+
+    press(&mut app, KeyCode::A);
+    assert_eq!(*app.world.resource::<PlayMode>(), PlayMode::Auto);
+    press(&mut app, KeyCode::A);
+    assert_eq!(*app.world.resource::<PlayMode>(), PlayMode::Normal);
+    assert_eq!(texts(&app), vec!["「一」"]);
+  }
+
+  #[test]
+  fn test_pause_in_backlog() {
+    let mut app = setup(PlayerConfig {
+      auto_delay: Duration::ZERO,
+      auto_char_delay: Duration::ZERO,
+      ..Default::default()
+    });
+    advance(&mut app);
+    app.update();
+    press(&mut app, KeyCode::L);
+
+    // the keys are taken by the backlog while it is open
+    press(&mut app, KeyCode::A);
+    assert_eq!(*app.world.resource::<PlayMode>(), PlayMode::Normal);
+
+    *app.world.resource_mut::<PlayMode>() = PlayMode::Auto;
+    for _ in 0..5 {
+      app.update();
+    }
+    assert_eq!(texts(&app), vec!["「一」"]);
+    let player = &app.world.resource::<NahidaPlayer>().player;
+    assert_eq!(player.cursor().map(|x| x.frames[0].step), Some(0));
+
+    // and auto mode goes on once it is closed
+    press(&mut app, KeyCode::L);
+    for _ in 0..5 {
+      app.update();
+    }
+    assert_eq!(texts(&app), vec!["「一」", "「二」", "「三」"]);
+  }
+}
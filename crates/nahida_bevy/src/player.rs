@@ -0,0 +1,178 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use nahida_core::{
+  path::join_absolute_path,
+  player::{PlayerConfig, PlayerError, SeenSteps, StoryLoader, StoryPlayer},
+  story::{Story, StoryAction},
+};
+
+use crate::{
+  asset::story::StoryAsset,
+  backlog::{BacklogEntry, TextShown},
+  stage::RestoreStage,
+  state::{loading::NahidaResources, NahidaState},
+  NahidaEntryPoint,
+};
+
+pub struct NahidaPlayerPlugin;
+
+impl Plugin for NahidaPlayerPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<NahidaPlayerConfig>()
+      .init_resource::<NahidaSeen>()
+      .add_event::<AdvanceRequest>()
+      .add_event::<ChooseRequest>()
+      .add_event::<StepPlayed>()
+      .add_event::<StoryFinished>()
+      .add_startup_system(setup_player)
+      .add_system(advance_player.in_set(AdvanceSet))
+      .add_system(rollback.in_set(OnUpdate(NahidaState::Playing)));
+  }
+}
+
+/// set by the project before adding the plugin
+#[derive(Resource, Default)]
+pub struct NahidaPlayerConfig(pub PlayerConfig);
+
+/// progress of the game
+#[derive(Resource)]
+pub struct NahidaPlayer {
+  pub player: StoryPlayer,
+  /// whether the current step had been seen before it was entered,
+  /// skip mode stops at the ones that had not
+  pub was_seen: bool,
+}
+
+impl NahidaPlayer {
+  pub fn new(player: StoryPlayer) -> Self {
+    Self {
+      player,
+      was_seen: false,
+    }
+  }
+
+  /// start over from the entry point
+  pub fn restart(&mut self) {
+    let entry = self.player.entry().to_path_buf();
+    let config = self.player.config().clone();
+    self.player = StoryPlayer::new(entry).with_config(config);
+    self.was_seen = false;
+  }
+}
+
+/// steps ever shown, kept on disk along with the saves
+#[derive(Resource, Default)]
+pub struct NahidaSeen(pub SeenSteps);
+
+/// handles the requests to advance, systems sending them should run before it
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct AdvanceSet;
+
+/// go to the next step
+pub struct AdvanceRequest;
+
+/// pick an option of the choice in the current step
+pub struct ChooseRequest(pub usize);
+
+/// sent after a step is entered, to show it
+pub struct StepPlayed;
+
+/// sent when there is no step left
+pub struct StoryFinished;
+
+fn setup_player(
+  mut command: Commands,
+  entry_point: Res<NahidaEntryPoint>,
+  config: Res<NahidaPlayerConfig>,
+) {
+  let player = StoryPlayer::new(PathBuf::from(&entry_point.0)).with_config(config.0.clone());
+  command.insert_resource(NahidaPlayer::new(player));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn advance_player(
+  mut advances: EventReader<AdvanceRequest>,
+  mut choices: EventReader<ChooseRequest>,
+  mut player: ResMut<NahidaPlayer>,
+  mut seen: ResMut<NahidaSeen>,
+  resources: Res<NahidaResources>,
+  assets: Res<Assets<StoryAsset>>,
+  mut played: EventWriter<StepPlayed>,
+  mut finished: EventWriter<StoryFinished>,
+  mut shown: EventWriter<TextShown>,
+) {
+  let loader = StoryAssets {
+    resources: &resources,
+    assets: &assets,
+  };
+
+  // move once however many requests are made in a frame
+  let result = match (choices.iter().last(), advances.iter().count()) {
+    (Some(ChooseRequest(option)), _) => player.player.choose(&loader, *option),
+    (None, 0) => return,
+    (None, _) => player.player.advance(&loader),
+  };
+  let step = match result {
+    Ok(Some(step)) => step,
+    Ok(None) => return finished.send(StoryFinished),
+    Err(PlayerError::ChoicePending) => return,
+    Err(e) => return error!("Failed to play: {e}"),
+  };
+  let Some(cursor) = player.player.cursor() else {
+    return;
+  };
+
+  let new = seen.bypass_change_detection().0.insert(cursor);
+  if new {
+    seen.set_changed();
+  }
+
+  for action in &step.actions {
+    if let StoryAction::Text { name, text, voice } = action {
+      shown.send(TextShown(BacklogEntry {
+        name: name.clone(),
+        text: text.clone(),
+        voice: voice
+          .as_ref()
+          .and_then(|url| join_absolute_path(&cursor.path, url)),
+      }));
+    }
+  }
+  player.was_seen = !new;
+  played.send(StepPlayed);
+}
+
+/// step back with backspace
+fn rollback(
+  keys: Res<Input<KeyCode>>,
+  mut player: ResMut<NahidaPlayer>,
+  mut restore: EventWriter<RestoreStage>,
+) {
+  if !keys.just_pressed(KeyCode::Back) {
+    return;
+  }
+
+  match player.player.rollback() {
+    Ok(()) => {
+      // the step rolled back to has been shown already
+      player.was_seen = true;
+      restore.send(RestoreStage(player.player.stage().snapshot()));
+    }
+    Err(e) => info!("Cannot roll back: {e}"),
+  }
+}
+
+/// the loaded story files, for the player
+pub struct StoryAssets<'a> {
+  pub resources: &'a NahidaResources,
+  pub assets: &'a Assets<StoryAsset>,
+}
+
+impl StoryLoader for StoryAssets<'_> {
+  fn load(&self, path: &Path) -> Option<&Story> {
+    let handle = self.resources.story(path)?;
+    self.assets.get(handle).map(|x| &x.story)
+  }
+}
@@ -0,0 +1,645 @@
+use std::{
+  collections::BTreeMap,
+  fs, io,
+  path::PathBuf,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use nahida_core::{
+  player::{Cursor, PlayerError, SeenSteps, StoryPlayer},
+  stage::Stage,
+  StageSnapshot,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+  asset::story::StoryAsset,
+  backlog::Backlog,
+  data_dir,
+  player::{NahidaPlayer, NahidaSeen, StoryAssets},
+  stage::RestoreStage,
+  state::{loading::NahidaResources, NahidaState},
+  storage_from_world,
+};
+
+pub struct NahidaSavePlugin;
+
+impl Plugin for NahidaSavePlugin {
+  fn build(&self, app: &mut App) {
+    if !app.world.contains_resource::<SaveStorage>() {
+      if let Some(storage) = storage_from_world(&app.world, "saves", SaveStorage::new) {
+        app.insert_resource(storage);
+      }
+    }
+
+    app
+      .add_event::<SaveRequest>()
+      .add_event::<LoadRequest>()
+      .init_resource::<SeenFlush>()
+      .add_startup_system(load_seen)
+      // after everything that may send `AppExit`
+      .add_system(save_seen.in_base_set(CoreSet::Last))
+      .add_system(flush_seen.in_schedule(OnExit(NahidaState::Playing)))
+      .add_system(
+        quick_save_keys
+          .before(save_game)
+          .before(load_game)
+          .in_set(OnUpdate(NahidaState::Playing)),
+      )
+      .add_system(save_game)
+      .add_system(load_game);
+  }
+}
+
+#[derive(Debug, Error)]
+pub enum SaveError {
+  #[error("failed to access the save: {0}")]
+  Io(#[from] io::Error),
+  #[error("failed to write the save: {0}")]
+  Serialize(#[from] ron::Error),
+  #[error("broken save: {0}")]
+  Deserialize(#[from] ron::error::SpannedError),
+  #[error("nothing to save yet")]
+  NotStarted,
+  #[error("no place to keep the saves")]
+  NoStorage,
+  #[error("save does not fit the story: {0}")]
+  Player(#[from] PlayerError),
+}
+
+/// everything needed to continue the game later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveData {
+  /// the story file and the step in it
+  pub cursor: Cursor,
+  pub stage: StageSnapshot,
+  pub variables: BTreeMap<String, String>,
+  /// seconds since the unix epoch
+  pub timestamp: u64,
+}
+
+impl SaveData {
+  pub fn new(player: &NahidaPlayer) -> Result<Self, SaveError> {
+    let cursor = player.player.cursor().ok_or(SaveError::NotStarted)?;
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|x| x.as_secs())
+      .unwrap_or_default();
+
+    Ok(Self {
+      cursor: cursor.clone(),
+      stage: player.player.stage().snapshot(),
+      variables: player.player.variables().clone(),
+      timestamp,
+    })
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SaveSlot {
+  Quick,
+  Numbered(u32),
+}
+
+impl SaveSlot {
+  fn file_name(&self) -> String {
+    match self {
+      SaveSlot::Quick => "quick.ron".to_string(),
+      SaveSlot::Numbered(n) => format!("slot-{n}.ron"),
+    }
+  }
+
+  fn from_file_name(name: &str) -> Option<Self> {
+    match name {
+      "quick.ron" => Some(SaveSlot::Quick),
+      _ => name
+        .strip_prefix("slot-")?
+        .strip_suffix(".ron")?
+        .parse()
+        .ok()
+        .map(SaveSlot::Numbered),
+    }
+  }
+}
+
+/// where the saves are kept on disk
+#[derive(Resource, Debug, Clone)]
+pub struct SaveStorage {
+  pub dir: PathBuf,
+}
+
+impl SaveStorage {
+  /// `saves` in the data directory of the app,
+  /// `None` when the platform has no data directory
+  pub fn new(app: &str) -> Option<Self> {
+    Some(Self {
+      dir: data_dir(app)?.join("saves"),
+    })
+  }
+
+  pub fn save(&self, slot: SaveSlot, data: &SaveData) -> Result<(), SaveError> {
+    self.write(&slot.file_name(), data)
+  }
+
+  pub fn load(&self, slot: SaveSlot) -> Result<SaveData, SaveError> {
+    self.read(&slot.file_name())
+  }
+
+  pub fn save_seen(&self, seen: &SeenSteps) -> Result<(), SaveError> {
+    self.write("seen.ron", seen)
+  }
+
+  /// nothing is seen before the first run
+  pub fn load_seen(&self) -> Result<SeenSteps, SaveError> {
+    match self.read("seen.ron") {
+      Err(SaveError::Io(e)) if e.kind() == io::ErrorKind::NotFound => Ok(SeenSteps::default()),
+      result => result,
+    }
+  }
+
+  fn write<T: Serialize>(&self, file_name: &str, value: &T) -> Result<(), SaveError> {
+    let content = ron::ser::to_string_pretty(value, Default::default())?;
+    fs::create_dir_all(&self.dir)?;
+    fs::write(self.dir.join(file_name), content)?;
+    Ok(())
+  }
+
+  fn read<T: DeserializeOwned>(&self, file_name: &str) -> Result<T, SaveError> {
+    let content = fs::read_to_string(self.dir.join(file_name))?;
+    Ok(ron::from_str(&content)?)
+  }
+
+  /// the save written last
+  pub fn latest(&self) -> Option<(SaveSlot, SaveData)> {
+    self
+      .slots()
+      .into_iter()
+      .filter_map(|slot| Some((slot, self.load(slot).ok()?)))
+      .max_by_key(|(_, data)| data.timestamp)
+  }
+
+  /// all the slots having a save, in order
+  pub fn slots(&self) -> Vec<SaveSlot> {
+    let Ok(entries) = fs::read_dir(&self.dir) else {
+      return vec![];
+    };
+
+    let mut slots = entries
+      .filter_map(|entry| SaveSlot::from_file_name(entry.ok()?.file_name().to_str()?))
+      .collect::<Vec<_>>();
+    slots.sort();
+    slots
+  }
+}
+
+pub struct SaveRequest(pub SaveSlot);
+
+pub struct LoadRequest(pub SaveSlot);
+
+fn quick_save_keys(
+  keys: Res<Input<KeyCode>>,
+  mut save: EventWriter<SaveRequest>,
+  mut load: EventWriter<LoadRequest>,
+) {
+  if keys.just_pressed(KeyCode::F5) {
+    save.send(SaveRequest(SaveSlot::Quick));
+  }
+  if keys.just_pressed(KeyCode::F9) {
+    load.send(LoadRequest(SaveSlot::Quick));
+  }
+}
+
+fn load_seen(mut seen: ResMut<NahidaSeen>, storage: Option<Res<SaveStorage>>) {
+  let Some(storage) = storage else {
+    return;
+  };
+
+  match storage.load_seen() {
+    Ok(steps) => seen.0 = steps,
+    Err(e) => error!("Failed to load the seen steps: {e}"),
+  }
+}
+
+/// seen steps are written together from time to time,
+/// since skipping may see a new step every frame
+#[derive(Resource)]
+struct SeenFlush {
+  timer: Timer,
+  pending: bool,
+}
+
+impl Default for SeenFlush {
+  fn default() -> Self {
+    Self {
+      timer: Timer::from_seconds(5.0, TimerMode::Repeating),
+      pending: false,
+    }
+  }
+}
+
+impl SeenFlush {
+  fn write(&mut self, seen: &NahidaSeen, storage: Option<&SaveStorage>) {
+    if !std::mem::take(&mut self.pending) {
+      return;
+    }
+    let Some(storage) = storage else {
+      return;
+    };
+
+    if let Err(e) = storage.save_seen(&seen.0) {
+      error!("Failed to save the seen steps: {e}");
+    }
+  }
+}
+
+/// write the seen steps on the timer, or before the app exits
+fn save_seen(
+  seen: Res<NahidaSeen>,
+  storage: Option<Res<SaveStorage>>,
+  mut flush: ResMut<SeenFlush>,
+  mut exit: EventReader<AppExit>,
+  time: Res<Time>,
+) {
+  if seen.is_changed() && !seen.is_added() {
+    flush.pending = true;
+  }
+
+  let exiting = exit.iter().count() > 0;
+  if flush.timer.tick(time.delta()).just_finished() || exiting {
+    flush.write(&seen, storage.as_deref());
+  }
+}
+
+fn flush_seen(
+  seen: Res<NahidaSeen>,
+  storage: Option<Res<SaveStorage>>,
+  mut flush: ResMut<SeenFlush>,
+) {
+  flush.write(&seen, storage.as_deref());
+}
+
+fn save_game(
+  mut events: EventReader<SaveRequest>,
+  player: Res<NahidaPlayer>,
+  storage: Option<Res<SaveStorage>>,
+) {
+  for SaveRequest(slot) in events.iter() {
+    let result = SaveData::new(&player).and_then(|data| {
+      let storage = storage.as_deref().ok_or(SaveError::NoStorage)?;
+      storage.save(*slot, &data)
+    });
+    match result {
+      Ok(()) => info!("Saved to {slot:?}"),
+      Err(e) => error!("Failed to save to {slot:?}: {e}"),
+    }
+  }
+}
+
+/// the stage is restored right away while playing,
+/// otherwise the story is entered and restores it
+#[allow(clippy::too_many_arguments)]
+fn load_game(
+  mut events: EventReader<LoadRequest>,
+  mut player: ResMut<NahidaPlayer>,
+  mut backlog: ResMut<Backlog>,
+  storage: Option<Res<SaveStorage>>,
+  mut restore: EventWriter<RestoreStage>,
+  resources: Res<NahidaResources>,
+  assets: Res<Assets<StoryAsset>>,
+  state: Res<State<NahidaState>>,
+  mut next_state: ResMut<NextState<NahidaState>>,
+) {
+  let loader = StoryAssets {
+    resources: &resources,
+    assets: &assets,
+  };
+
+  for LoadRequest(slot) in events.iter() {
+    let result = storage
+      .as_deref()
+      .ok_or(SaveError::NoStorage)
+      .and_then(|x| x.load(*slot))
+      .and_then(|data| player.load(data, &loader));
+    match result {
+      Ok(stage) if state.0 == NahidaState::Playing => restore.send(RestoreStage(stage)),
+      Ok(_) => {
+        backlog.clear();
+        next_state.set(NahidaState::Playing);
+      }
+      Err(e) => error!("Failed to load {slot:?}: {e}"),
+    }
+  }
+}
+
+impl NahidaPlayer {
+  /// continue from the save, returning the stage to restore
+  pub fn load(
+    &mut self,
+    mut data: SaveData,
+    loader: &StoryAssets,
+  ) -> Result<StageSnapshot, SaveError> {
+    // the script may have changed since the save
+    data.cursor.relocate(loader)?;
+
+    let entry = self.player.entry().to_path_buf();
+    let config = self.player.config().clone();
+    self.player =
+      StoryPlayer::restore(entry, data.cursor, Stage::from(&data.stage)).with_config(config);
+    *self.player.variables_mut() = data.variables;
+    // the step was on screen when it was saved
+    self.was_seen = true;
+    Ok(data.stage)
+  }
+}
+
+/// `YYYY-MM-DD hh:mm` in UTC
+pub fn format_timestamp(timestamp: u64) -> String {
+  let days = (timestamp / 86400) as i64;
+  let minutes = timestamp % 86400 / 60;
+
+  // civil date from the days since the epoch, by Howard Hinnant
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z - era * 146097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + (month <= 2) as i64;
+
+  format!(
+    "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+    minutes / 60,
+    minutes % 60
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+  };
+
+  use bevy::{app::AppExit, prelude::*};
+  use nahida_core::{
+    player::{Cursor, Frame, StoryPlayer},
+    stage::{ImageSnapshot, Stage},
+    story::StepId,
+    Color, Location, Position, Size, StageSnapshot,
+  };
+  use nahida_parser::parse_story;
+
+  use crate::{
+    asset::story::{StoryAsset, StoryAssetPlugin},
+    backlog::{Backlog, BacklogEntry},
+    player::{NahidaPlayer, NahidaSeen},
+    stage::RestoreStage,
+    state::{loading::NahidaResources, NahidaState},
+  };
+
+  use super::{format_timestamp, LoadRequest, NahidaSavePlugin, SaveData, SaveSlot, SaveStorage};
+
+  fn storage(name: &str) -> SaveStorage {
+    let dir = std::env::temp_dir().join(format!("nahida-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    SaveStorage { dir }
+  }
+
+  fn snapshot() -> StageSnapshot {
+    StageSnapshot {
+      bg: Some(ImageSnapshot {
+        url: PathBuf::from("/background.png"),
+        location: Location {
+          position: Position(0.5, 0.5),
+          size: Size::Cover,
+        },
+        rotation: 0.0,
+        scale: 1.0,
+        opacity: 0.5,
+        blur: 0.0,
+        tint: Color::WHITE,
+        animation: None,
+      }),
+      figures: vec![],
+      bgm: Some(PathBuf::from("/bgm.mp3")),
+    }
+  }
+
+  fn cursor(step: usize) -> Cursor {
+    Cursor {
+      path: PathBuf::from("/story.md"),
+      frames: vec![Frame {
+        step,
+        choice: None,
+        id: StepId::default(),
+      }],
+    }
+  }
+
+  #[test]
+  fn test_storage() {
+    let storage = storage("storage");
+    assert_eq!(storage.slots(), vec![]);
+
+    let data = SaveData {
+      cursor: cursor(3),
+      stage: snapshot(),
+      variables: BTreeMap::from([("name".to_string(), "nahida".to_string())]),
+      timestamp: 42,
+    };
+    let later = SaveData {
+      timestamp: 43,
+      ..data.clone()
+    };
+    storage.save(SaveSlot::Numbered(2), &later).unwrap();
+    storage.save(SaveSlot::Quick, &data).unwrap();
+
+    assert_eq!(
+      storage.slots(),
+      vec![SaveSlot::Quick, SaveSlot::Numbered(2)]
+    );
+    assert_eq!(storage.load(SaveSlot::Quick).unwrap(), data);
+    assert!(storage.load(SaveSlot::Numbered(1)).is_err());
+    assert_eq!(storage.latest(), Some((SaveSlot::Numbered(2), later)));
+
+    fs::remove_dir_all(&storage.dir).unwrap();
+  }
+
+  #[test]
+  fn test_format_timestamp() {
+    assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+    assert_eq!(format_timestamp(951825600), "2000-02-29 12:00");
+    assert_eq!(format_timestamp(1792281599), "2026-10-17 23:59");
+  }
+
+  #[test]
+  fn test_save_seen() {
+    let storage = storage("seen");
+
+    let mut app = App::new();
+    app
+      .add_plugins(MinimalPlugins)
+      .add_plugin(AssetPlugin::default())
+      .add_plugin(StoryAssetPlugin)
+      .add_state::<NahidaState>()
+      .init_resource::<Input<KeyCode>>()
+      .init_resource::<NahidaResources>()
+      .init_resource::<NahidaSeen>()
+      .init_resource::<Backlog>()
+      .add_event::<RestoreStage>()
+      .insert_resource(NahidaPlayer::new(StoryPlayer::new("/story.md".into())))
+      .add_plugin(NahidaSavePlugin)
+      .insert_resource(storage.clone());
+    app.update();
+
+    // nothing is written until the app exits
+    app.world.resource_mut::<NahidaSeen>().0.insert(&cursor(1));
+    app.update();
+    assert!(!storage.load_seen().unwrap().contains(&cursor(1)));
+
+    app.world.send_event(AppExit);
+    app.update();
+    assert!(storage.load_seen().unwrap().contains(&cursor(1)));
+
+    fs::remove_dir_all(&storage.dir).unwrap();
+  }
+
+  #[test]
+  fn test_quick_save_and_load() {
+    let storage = storage("quick");
+    let entry = PathBuf::from("/story.md");
+    let script = "「一」\n\n「二」\n\n「三」\n";
+    let stage = Stage::from(&snapshot());
+
+    let mut app = App::new();
+    app
+      .add_plugins(MinimalPlugins)
+      .add_plugin(AssetPlugin::default())
+      .add_plugin(StoryAssetPlugin)
+      .add_state::<NahidaState>()
+      .insert_resource(NextState(Some(NahidaState::Playing)))
+      .init_resource::<Input<KeyCode>>()
+      .init_resource::<NahidaResources>()
+      .init_resource::<NahidaSeen>()
+      .init_resource::<Backlog>()
+      .add_event::<RestoreStage>()
+      .add_plugin(NahidaSavePlugin)
+      .insert_resource(storage.clone());
+
+    let handle = app
+      .world
+      .resource_mut::<Assets<StoryAsset>>()
+      .add(StoryAsset {
+        story: parse_story(script).unwrap(),
+      });
+    let mut resources = app.world.resource_mut::<NahidaResources>();
+    resources.insert_story(entry.clone(), handle.clone());
+
+    // play to the second paragraph
+    let stories = HashMap::from([(entry.clone(), parse_story(script).unwrap())]);
+    let mut player = StoryPlayer::restore(entry.clone(), Cursor::new(entry.clone()), stage.clone());
+    player.advance(&stories).unwrap();
+    app.insert_resource(NahidaPlayer::new(player));
+
+    app
+      .world
+      .resource_mut::<Input<KeyCode>>()
+      .press(KeyCode::F5);
+    app.update();
+    assert_eq!(storage.slots(), vec![SaveSlot::Quick]);
+
+    // a paragraph is written before it after saving
+    app.world.resource_mut::<NahidaPlayer>().player =
+      StoryPlayer::restore(entry, cursor(0), Stage::default());
+    let mut assets = app.world.resource_mut::<Assets<StoryAsset>>();
+    assets.get_mut(&handle).unwrap().story = parse_story(&format!("「零」\n\n{script}")).unwrap();
+
+    let mut input = app.world.resource_mut::<Input<KeyCode>>();
+    input.release(KeyCode::F5);
+    input.clear();
+    input.press(KeyCode::F9);
+    app.update();
+
+    let player = &app.world.resource::<NahidaPlayer>().player;
+    let frames = &player.cursor().unwrap().frames;
+    assert_eq!(frames.iter().map(|x| x.step).collect::<Vec<_>>(), vec![2]);
+    assert_eq!(player.stage(), &stage);
+
+    let events = app.world.resource::<Events<RestoreStage>>();
+    let mut reader = events.get_reader();
+    let restored = reader.iter(events).last().map(|x| &x.0);
+    assert_eq!(restored, Some(&snapshot()));
+
+    fs::remove_dir_all(&storage.dir).unwrap();
+  }
+
+  #[test]
+  fn test_load_from_menu() {
+    let storage = storage("menu");
+    let entry = PathBuf::from("/story.md");
+    let script = "「一」\n\n「二」\n\n「三」\n";
+
+    let mut app = App::new();
+    app
+      .add_plugins(MinimalPlugins)
+      .add_plugin(AssetPlugin::default())
+      .add_plugin(StoryAssetPlugin)
+      .add_state::<NahidaState>()
+      .insert_resource(NextState(Some(NahidaState::Menu)))
+      .init_resource::<Input<KeyCode>>()
+      .init_resource::<NahidaResources>()
+      .init_resource::<NahidaSeen>()
+      .init_resource::<Backlog>()
+      .add_event::<RestoreStage>()
+      .add_plugin(NahidaSavePlugin)
+      .insert_resource(storage.clone());
+
+    let handle = app
+      .world
+      .resource_mut::<Assets<StoryAsset>>()
+      .add(StoryAsset {
+        story: parse_story(script).unwrap(),
+      });
+    let mut resources = app.world.resource_mut::<NahidaResources>();
+    resources.insert_story(entry.clone(), handle);
+
+    // a save of the second paragraph
+    let stories = HashMap::from([(entry.clone(), parse_story(script).unwrap())]);
+    let mut player = StoryPlayer::new(entry.clone());
+    player.advance(&stories).unwrap();
+    player.advance(&stories).unwrap();
+    let data = SaveData::new(&NahidaPlayer::new(player)).unwrap();
+    storage.save(SaveSlot::Numbered(1), &data).unwrap();
+
+    app.insert_resource(NahidaPlayer::new(StoryPlayer::new(entry)));
+    app.world.resource_mut::<Backlog>().push(BacklogEntry {
+      name: None,
+      text: "「零」".into(),
+      voice: None,
+    });
+    app.update();
+
+    app.world.send_event(LoadRequest(SaveSlot::Numbered(1)));
+    app.update();
+    app.update();
+
+    // the stage is left to the story, not spawned over the title screen
+    assert_eq!(
+      app.world.resource::<State<NahidaState>>().0,
+      NahidaState::Playing
+    );
+    let events = app.world.resource::<Events<RestoreStage>>();
+    assert!(events.get_reader().iter(events).next().is_none());
+    assert!(app.world.resource::<Backlog>().is_empty());
+
+    let player = &app.world.resource::<NahidaPlayer>().player;
+    assert_eq!(player.cursor().map(|x| x.frames[0].step), Some(1));
+
+    fs::remove_dir_all(&storage.dir).unwrap();
+  }
+}
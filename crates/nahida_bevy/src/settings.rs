@@ -0,0 +1,559 @@
+use std::{fs, io, path::PathBuf, time::Duration};
+
+use bevy::{
+  prelude::*,
+  ui::FocusPolicy,
+  window::{PrimaryWindow, WindowMode},
+};
+use nahida_core::player::PlayerConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  data_dir,
+  player::{NahidaPlayer, NahidaPlayerConfig},
+  save::SaveError,
+  stage::NahidaBgm,
+  state::{loading::NahidaFonts, menu::OpenSettings, playing::NahidaVoice},
+  storage_from_world,
+  text::Typewriter,
+};
+
+pub struct NahidaSettingsPlugin;
+
+impl Plugin for NahidaSettingsPlugin {
+  fn build(&self, app: &mut App) {
+    if !app.world.contains_resource::<SettingsStorage>() {
+      if let Some(storage) = storage_from_world(&app.world, "settings", SettingsStorage::new) {
+        app.insert_resource(storage);
+      }
+    }
+
+    app
+      .init_resource::<NahidaSettings>()
+      .add_startup_system(load_settings)
+      .add_system(save_settings)
+      .add_system(apply_settings)
+      .add_system(open_settings)
+      .add_system(click_setting_button)
+      .add_system(sync_setting_values.after(click_setting_button))
+      .add_system(close_settings);
+  }
+}
+
+/// preferences of the player, kept across runs
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NahidaSettings {
+  /// volumes from 0 to 1, the others are scaled by the master volume
+  pub master_volume: f32,
+  pub bgm_volume: f32,
+  pub sfx_volume: f32,
+  pub voice_volume: f32,
+  /// characters typed per second
+  pub text_speed: f32,
+  /// time to wait in auto mode, besides reading the text
+  pub auto_delay: Duration,
+  pub fullscreen: bool,
+  /// whether skip mode goes through the steps not seen yet
+  pub skip_unread: bool,
+}
+
+impl Default for NahidaSettings {
+  fn default() -> Self {
+    Self::new(&PlayerConfig::default())
+  }
+}
+
+/// kind of sound, each with its own volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioChannel {
+  Bgm,
+  Sfx,
+  Voice,
+}
+
+impl NahidaSettings {
+  /// settings before the player changes anything, following the project
+  pub fn new(config: &PlayerConfig) -> Self {
+    Self {
+      master_volume: 1.0,
+      bgm_volume: 1.0,
+      sfx_volume: 1.0,
+      voice_volume: 1.0,
+      text_speed: config.text_speed,
+      auto_delay: config.auto_delay,
+      fullscreen: false,
+      skip_unread: config.skip_unread,
+    }
+  }
+
+  /// volume to play the sound at
+  pub fn volume(&self, channel: AudioChannel) -> f32 {
+    let volume = match channel {
+      AudioChannel::Bgm => self.bgm_volume,
+      AudioChannel::Sfx => self.sfx_volume,
+      AudioChannel::Voice => self.voice_volume,
+    };
+    self.master_volume * volume
+  }
+
+  /// override the project config with the settings
+  pub fn apply(&self, config: &mut PlayerConfig) {
+    config.text_speed = self.text_speed;
+    config.auto_delay = self.auto_delay;
+    config.skip_unread = self.skip_unread;
+  }
+}
+
+/// where the settings are kept on disk
+#[derive(Resource, Debug, Clone)]
+pub struct SettingsStorage {
+  pub path: PathBuf,
+}
+
+impl SettingsStorage {
+  /// `settings.ron` in the data directory of the app,
+  /// `None` when the platform has no data directory
+  pub fn new(app: &str) -> Option<Self> {
+    Some(Self {
+      path: data_dir(app)?.join("settings.ron"),
+    })
+  }
+
+  pub fn save(&self, settings: &NahidaSettings) -> Result<(), SaveError> {
+    let content = ron::ser::to_string_pretty(settings, Default::default())?;
+    if let Some(dir) = self.path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    fs::write(&self.path, content)?;
+    Ok(())
+  }
+
+  /// `None` before the first run
+  pub fn load(&self) -> Result<Option<NahidaSettings>, SaveError> {
+    match fs::read_to_string(&self.path) {
+      Ok(content) => Ok(Some(ron::from_str(&content)?)),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(e.into()),
+    }
+  }
+}
+
+fn load_settings(
+  mut settings: ResMut<NahidaSettings>,
+  storage: Option<Res<SettingsStorage>>,
+  config: Res<NahidaPlayerConfig>,
+) {
+  let loaded = storage.map_or(Ok(None), |x| x.load());
+  *settings = match loaded {
+    Ok(Some(loaded)) => loaded,
+    Ok(None) => NahidaSettings::new(&config.0),
+    Err(e) => {
+      error!("Failed to load the settings: {e}");
+      NahidaSettings::new(&config.0)
+    }
+  };
+}
+
+fn save_settings(settings: Res<NahidaSettings>, storage: Option<Res<SettingsStorage>>) {
+  let Some(storage) = storage else {
+    return;
+  };
+  if !settings.is_changed() || settings.is_added() {
+    return;
+  }
+
+  if let Err(e) = storage.save(&settings) {
+    error!("Failed to save the settings: {e}");
+  }
+}
+
+/// follow the settings as soon as they change
+fn apply_settings(
+  settings: Res<NahidaSettings>,
+  player: Option<ResMut<NahidaPlayer>>,
+  mut windows: Query<&mut Window, With<PrimaryWindow>>,
+  mut bgm: ResMut<NahidaBgm>,
+  voice: Res<NahidaVoice>,
+  sinks: Res<Assets<AudioSink>>,
+  mut typewriters: Query<&mut Typewriter>,
+) {
+  // the player is set up after the settings are loaded
+  let Some(mut player) = player else {
+    return;
+  };
+  if !settings.is_changed() && !player.is_added() {
+    return;
+  }
+
+  settings.apply(player.player.config_mut());
+  for mut typewriter in typewriters.iter_mut() {
+    if typewriter.speed() != settings.text_speed {
+      typewriter.set_speed(settings.text_speed);
+    }
+  }
+
+  bgm.set_volume(settings.volume(AudioChannel::Bgm), &sinks);
+  if let Some(sink) = voice.0.as_ref().and_then(|x| sinks.get(x)) {
+    sink.set_volume(settings.volume(AudioChannel::Voice));
+  }
+
+  let mode = match settings.fullscreen {
+    true => WindowMode::BorderlessFullscreen,
+    false => WindowMode::Windowed,
+  };
+  for mut window in windows.iter_mut() {
+    if window.mode != mode {
+      window.mode = mode;
+    }
+  }
+}
+
+/// a line of the settings screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingItem {
+  MasterVolume,
+  BgmVolume,
+  SfxVolume,
+  VoiceVolume,
+  TextSpeed,
+  AutoDelay,
+  Fullscreen,
+  SkipUnread,
+}
+
+impl SettingItem {
+  const ALL: [SettingItem; 8] = [
+    SettingItem::MasterVolume,
+    SettingItem::BgmVolume,
+    SettingItem::SfxVolume,
+    SettingItem::VoiceVolume,
+    SettingItem::TextSpeed,
+    SettingItem::AutoDelay,
+    SettingItem::Fullscreen,
+    SettingItem::SkipUnread,
+  ];
+
+  fn label(&self) -> &'static str {
+    match self {
+      SettingItem::MasterVolume => "总音量",
+      SettingItem::BgmVolume => "音乐音量",
+      SettingItem::SfxVolume => "音效音量",
+      SettingItem::VoiceVolume => "语音音量",
+      SettingItem::TextSpeed => "文字速度",
+      SettingItem::AutoDelay => "自动播放等待",
+      SettingItem::Fullscreen => "全屏",
+      SettingItem::SkipUnread => "快进未读文本",
+    }
+  }
+
+  fn value(&self, settings: &NahidaSettings) -> String {
+    let switch = |x: bool| if x { "开" } else { "关" }.to_string();
+
+    match self {
+      SettingItem::MasterVolume => format!("{:.0}%", settings.master_volume * 100.0),
+      SettingItem::BgmVolume => format!("{:.0}%", settings.bgm_volume * 100.0),
+      SettingItem::SfxVolume => format!("{:.0}%", settings.sfx_volume * 100.0),
+      SettingItem::VoiceVolume => format!("{:.0}%", settings.voice_volume * 100.0),
+      SettingItem::TextSpeed if settings.text_speed.is_infinite() => "瞬间".to_string(),
+      SettingItem::TextSpeed => format!("{:.0} 字/秒", settings.text_speed),
+      SettingItem::AutoDelay => format!("{:.1} 秒", settings.auto_delay.as_secs_f32()),
+      SettingItem::Fullscreen => switch(settings.fullscreen),
+      SettingItem::SkipUnread => switch(settings.skip_unread),
+    }
+  }
+
+  /// go up or down a step, switches are flipped either way
+  fn adjust(&self, settings: &mut NahidaSettings, up: bool) {
+    let step = |value: f32, step: f32, min: f32, max: f32| {
+      let value = value + if up { step } else { -step };
+      // snap to the step, so that the float errors do not pile up
+      ((value / step).round() * step).clamp(min, max)
+    };
+
+    match self {
+      SettingItem::MasterVolume => {
+        settings.master_volume = step(settings.master_volume, 0.1, 0.0, 1.0)
+      }
+      SettingItem::BgmVolume => settings.bgm_volume = step(settings.bgm_volume, 0.1, 0.0, 1.0),
+      SettingItem::SfxVolume => settings.sfx_volume = step(settings.sfx_volume, 0.1, 0.0, 1.0),
+      SettingItem::VoiceVolume => {
+        settings.voice_volume = step(settings.voice_volume, 0.1, 0.0, 1.0)
+      }
+      SettingItem::TextSpeed => {
+        // one step past the fastest speed shows the text at once
+        settings.text_speed = match (settings.text_speed.is_infinite(), up) {
+          (true, true) => f32::INFINITY,
+          (true, false) => 120.0,
+          (false, true) if settings.text_speed >= 120.0 => f32::INFINITY,
+          (false, _) => step(settings.text_speed, 10.0, 10.0, 120.0),
+        }
+      }
+      SettingItem::AutoDelay => {
+        let delay = step(settings.auto_delay.as_secs_f32(), 0.5, 0.0, 5.0);
+        settings.auto_delay = Duration::from_secs_f32(delay);
+      }
+      SettingItem::Fullscreen => settings.fullscreen = !settings.fullscreen,
+      SettingItem::SkipUnread => settings.skip_unread = !settings.skip_unread,
+    }
+  }
+}
+
+/// the settings screen, covering everything while it is open
+#[derive(Component)]
+pub struct SettingsScreen;
+
+#[derive(Component)]
+struct SettingButton {
+  item: SettingItem,
+  up: bool,
+}
+
+#[derive(Component)]
+struct SettingValue(SettingItem);
+
+#[derive(Component)]
+struct CloseButton;
+
+fn open_settings(
+  mut command: Commands,
+  mut events: EventReader<OpenSettings>,
+  screens: Query<(), With<SettingsScreen>>,
+  settings: Res<NahidaSettings>,
+  fonts: Res<NahidaFonts>,
+) {
+  if events.iter().count() == 0 || !screens.is_empty() {
+    return;
+  }
+
+  let style = TextStyle {
+    font: fonts.hanyi.clone(),
+    font_size: 28.0,
+    color: Color::WHITE,
+  };
+  let button = |size: f32| ButtonBundle {
+    style: Style {
+      size: Size::new(Val::Px(size), Val::Px(40.0)),
+      margin: UiRect::horizontal(Val::Px(8.0)),
+      justify_content: JustifyContent::Center,
+      align_items: AlignItems::Center,
+      ..Default::default()
+    },
+    background_color: Color::rgba(1.0, 1.0, 1.0, 0.2).into(),
+    ..Default::default()
+  };
+
+  command
+    .spawn((
+      SettingsScreen,
+      NodeBundle {
+        style: Style {
+          position_type: PositionType::Absolute,
+          size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+          flex_direction: FlexDirection::Column,
+          align_items: AlignItems::Center,
+          justify_content: JustifyContent::Center,
+          ..Default::default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.9).into(),
+        focus_policy: FocusPolicy::Block,
+        z_index: ZIndex::Global(20),
+        ..Default::default()
+      },
+    ))
+    .with_children(|screen| {
+      for item in SettingItem::ALL {
+        screen
+          .spawn(NodeBundle {
+            style: Style {
+              align_items: AlignItems::Center,
+              margin: UiRect::vertical(Val::Px(6.0)),
+              ..Default::default()
+            },
+            ..Default::default()
+          })
+          .with_children(|row| {
+            row.spawn(
+              TextBundle::from_section(item.label(), style.clone()).with_style(Style {
+                size: Size::width(Val::Px(240.0)),
+                ..Default::default()
+              }),
+            );
+            for up in [false, true] {
+              if up {
+                row.spawn((
+                  SettingValue(item),
+                  TextBundle::from_section(item.value(&settings), style.clone()).with_style(
+                    Style {
+                      size: Size::width(Val::Px(160.0)),
+                      justify_content: JustifyContent::Center,
+                      ..Default::default()
+                    },
+                  ),
+                ));
+              }
+              row
+                .spawn((SettingButton { item, up }, button(40.0)))
+                .with_children(|button| {
+                  let label = if up { "+" } else { "-" };
+                  button.spawn(TextBundle::from_section(label, style.clone()));
+                });
+            }
+          });
+      }
+
+      screen
+        .spawn((CloseButton, button(160.0)))
+        .with_children(|button| {
+          button.spawn(TextBundle::from_section("返回", style.clone()));
+        });
+    });
+}
+
+fn click_setting_button(
+  buttons: Query<(&Interaction, &SettingButton), Changed<Interaction>>,
+  mut settings: ResMut<NahidaSettings>,
+) {
+  for (interaction, button) in buttons.iter() {
+    if *interaction == Interaction::Clicked {
+      button.item.adjust(&mut settings, button.up);
+    }
+  }
+}
+
+fn sync_setting_values(
+  settings: Res<NahidaSettings>,
+  mut values: Query<(&mut Text, &SettingValue)>,
+) {
+  if !settings.is_changed() {
+    return;
+  }
+
+  for (mut text, SettingValue(item)) in values.iter_mut() {
+    text.sections[0].value = item.value(&settings);
+  }
+}
+
+/// the close button, `Esc` or right click leaves the settings
+fn close_settings(
+  mut command: Commands,
+  keys: Res<Input<KeyCode>>,
+  mouse: Res<Input<MouseButton>>,
+  buttons: Query<&Interaction, (With<CloseButton>, Changed<Interaction>)>,
+  screens: Query<Entity, With<SettingsScreen>>,
+) {
+  let clicked = buttons.iter().any(|x| *x == Interaction::Clicked);
+  if !(clicked || keys.just_pressed(KeyCode::Escape) || mouse.just_pressed(MouseButton::Right)) {
+    return;
+  }
+
+  for entity in screens.iter() {
+    command.entity(entity).despawn_recursive();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, time::Duration};
+
+  use bevy::prelude::*;
+  use nahida_core::{
+    player::{PlayerConfig, StoryPlayer},
+    text::{RichText, TextSpan},
+  };
+
+  use crate::{
+    player::{NahidaPlayer, NahidaPlayerConfig},
+    stage::NahidaBgm,
+    state::{loading::NahidaFonts, menu::OpenSettings, playing::NahidaVoice},
+    text::Typewriter,
+  };
+
+  use super::{NahidaSettings, NahidaSettingsPlugin, SettingItem, SettingsStorage};
+
+  fn storage(name: &str) -> SettingsStorage {
+    let dir = std::env::temp_dir().join(format!("nahida-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    SettingsStorage {
+      path: dir.join("settings.ron"),
+    }
+  }
+
+  #[test]
+  fn test_adjust() {
+    let mut settings = NahidaSettings::default();
+    SettingItem::MasterVolume.adjust(&mut settings, true);
+    assert_eq!(settings.master_volume, 1.0);
+    for _ in 0..3 {
+      SettingItem::MasterVolume.adjust(&mut settings, false);
+    }
+    assert_eq!(SettingItem::MasterVolume.value(&settings), "70%");
+
+    settings.text_speed = f32::INFINITY;
+    assert_eq!(SettingItem::TextSpeed.value(&settings), "瞬间");
+    SettingItem::TextSpeed.adjust(&mut settings, false);
+    assert_eq!(settings.text_speed, 120.0);
+    SettingItem::TextSpeed.adjust(&mut settings, false);
+    assert_eq!(settings.text_speed, 110.0);
+    SettingItem::TextSpeed.adjust(&mut settings, true);
+    SettingItem::TextSpeed.adjust(&mut settings, true);
+    assert_eq!(SettingItem::TextSpeed.value(&settings), "瞬间");
+    SettingItem::TextSpeed.adjust(&mut settings, true);
+    assert!(settings.text_speed.is_infinite());
+
+    SettingItem::SkipUnread.adjust(&mut settings, false);
+    assert!(settings.skip_unread);
+  }
+
+  #[test]
+  fn test_settings() {
+    let storage = storage("settings");
+    let config = PlayerConfig {
+      text_speed: 50.0,
+      ..Default::default()
+    };
+
+    let mut app = App::new();
+    app
+      .add_plugins(MinimalPlugins)
+      .add_plugin(AssetPlugin::default())
+      .add_asset::<AudioSink>()
+      .init_resource::<Input<KeyCode>>()
+      .init_resource::<Input<MouseButton>>()
+      .init_resource::<NahidaFonts>()
+      .init_resource::<NahidaBgm>()
+      .init_resource::<NahidaVoice>()
+      .add_event::<OpenSettings>()
+      .insert_resource(NahidaPlayerConfig(config))
+      .insert_resource(storage.clone())
+      .add_plugin(NahidaSettingsPlugin);
+
+    // the project config is followed before anything is changed
+    app.update();
+    assert_eq!(app.world.resource::<NahidaSettings>().text_speed, 50.0);
+    assert!(!storage.path.exists());
+
+    let text = RichText::new(vec![TextSpan::plain("「一二三四」")]);
+    let typewriter = app.world.spawn(Typewriter::new(text, 50.0)).id();
+
+    // the settings are applied to the player once it is set up
+    let mut settings = app.world.resource_mut::<NahidaSettings>();
+    settings.text_speed = 80.0;
+    settings.auto_delay = Duration::from_secs(3);
+    app.insert_resource(NahidaPlayer::new(StoryPlayer::new("/story.md".into())));
+    app.update();
+
+    let config = app.world.resource::<NahidaPlayer>().player.config();
+    assert_eq!(config.text_speed, 80.0);
+    assert_eq!(config.auto_delay, Duration::from_secs(3));
+    // and to the text being typed
+    let typewriter = app.world.get::<Typewriter>(typewriter).unwrap();
+    assert_eq!(typewriter.speed(), 80.0);
+    assert_eq!(
+      storage.load().unwrap(),
+      Some(app.world.resource::<NahidaSettings>().clone())
+    );
+
+    fs::remove_dir_all(storage.path.parent().unwrap()).unwrap();
+  }
+}
@@ -0,0 +1,85 @@
+@group(1) @binding(0)
+var<uniform> tint: vec4<f32>;
+@group(1) @binding(1)
+var<uniform> opacity: f32;
+@group(1) @binding(2)
+var<uniform> blur: f32;
+@group(1) @binding(3)
+var base_color_texture: texture_2d<f32>;
+@group(1) @binding(4)
+var base_color_sampler: sampler;
+@group(1) @binding(5)
+var<uniform> transition: u32;
+@group(1) @binding(6)
+var<uniform> progress: f32;
+@group(1) @binding(7)
+var mask_texture: texture_2d<f32>;
+@group(1) @binding(8)
+var mask_sampler: sampler;
+@group(1) @binding(9)
+var<uniform> softness: f32;
+
+// 9 x 9 taps, spread so that the outermost ones reach the radius
+fn gaussian_blur(uv: vec2<f32>, radius: f32) -> vec4<f32> {
+    let texel = radius / 4.0 / vec2<f32>(textureDimensions(base_color_texture));
+    var color = vec4<f32>(0.0);
+    var total = 0.0;
+
+    for (var x = -4; x <= 4; x = x + 1) {
+        for (var y = -4; y <= 4; y = y + 1) {
+            let offset = vec2<f32>(f32(x), f32(y));
+            let weight = exp(-dot(offset, offset) / 8.0);
+            color = color + textureSampleLevel(base_color_texture, base_color_sampler, uv + offset * texel, 0.0) * weight;
+            total = total + weight;
+        }
+    }
+
+    return color / total;
+}
+
+// angle from the top going clockwise, from 0 to 1
+fn conic_angle(uv: vec2<f32>) -> f32 {
+    let d = uv - vec2<f32>(0.5, 0.5);
+    return fract(atan2(d.x, -d.y) / 6.28318530718 + 1.0);
+}
+
+// reveals where `x` is below the progress, with a soft edge
+fn reveal(x: f32, progress: f32, edge: f32) -> f32 {
+    let edge = max(edge, 0.0001);
+    let threshold = progress * (1.0 + edge);
+    return 1.0 - smoothstep(threshold - edge, threshold, x);
+}
+
+// visibility of the pixel during the transition, see `TransitionType`
+fn transition_mask(uv: vec2<f32>) -> f32 {
+    switch transition {
+        // fade-in / fade-out
+        case 1u: { return progress; }
+        case 2u: { return 1.0 - progress; }
+        // conic-in / conic-out
+        case 3u: { return reveal(conic_angle(uv), progress, 0.02); }
+        case 4u: { return 1.0 - reveal(conic_angle(uv), progress, 0.02); }
+        // blinds-in / blinds-out
+        case 5u: { return reveal(fract(uv.y * 10.0), progress, 0.02); }
+        case 6u: { return 1.0 - reveal(fract(uv.y * 10.0), progress, 0.02); }
+        // rule
+        case 7u: {
+            let mask = textureSampleLevel(mask_texture, mask_sampler, uv, 0.0).r;
+            return reveal(mask, progress, softness);
+        }
+        default: { return 1.0; }
+    }
+}
+
+@fragment
+fn fragment(
+    @location(2) uv: vec2<f32>,
+) -> @location(0) vec4<f32> {
+    var color = textureSampleLevel(base_color_texture, base_color_sampler, uv, 0.0);
+    if blur > 0.0 {
+        color = gaussian_blur(uv, blur);
+    }
+
+    let tinted = mix(color.rgb, color.rgb * tint.rgb, tint.a);
+    return vec4<f32>(tinted, color.a * opacity * transition_mask(uv));
+}
@@ -0,0 +1,369 @@
+use std::path::Path;
+
+use bevy::{ecs::system::EntityCommands, prelude::*, sprite::MaterialMesh2dBundle};
+use nahida_core::{
+  path::join_absolute_path,
+  stage::ImageSnapshot,
+  story::{Animation, StoryAction, Transition, TransitionType},
+  StageSnapshot,
+};
+
+use crate::{
+  asset::story::StoryAsset,
+  material::stage::StageMaterial,
+  player::{NahidaPlayer, StepPlayed, StoryAssets},
+  settings::{AudioChannel, NahidaSettings},
+  state::loading::NahidaResources,
+  ImageAnimation, ImageTransition, Location, OriginalImage,
+};
+
+pub struct NahidaStagePlugin;
+
+impl Plugin for NahidaStagePlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_event::<RestoreStage>()
+      .init_resource::<NahidaBgm>()
+      .add_system(restore_stage)
+      .add_system(play_stage)
+      .add_system(despawn_outgoing);
+  }
+}
+
+/// rebuild the stage as it is in the snapshot, without playing any transition
+pub struct RestoreStage(pub StageSnapshot);
+
+/// background or figure on the stage
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub enum StageComponent {
+  Bg,
+  Fig(String),
+}
+
+/// image replaced or removed, kept until the transitions are over
+#[derive(Component)]
+pub struct Outgoing;
+
+/// the bgm playing
+#[derive(Resource)]
+pub struct NahidaBgm {
+  sink: Option<Handle<AudioSink>>,
+  volume: f32,
+}
+
+impl Default for NahidaBgm {
+  fn default() -> Self {
+    Self {
+      sink: None,
+      volume: 1.0,
+    }
+  }
+}
+
+impl NahidaBgm {
+  /// loop the new bgm in place of the old one
+  pub(crate) fn play(
+    &mut self,
+    source: Option<&Handle<AudioSource>>,
+    audio: &Audio,
+    sinks: &Assets<AudioSink>,
+  ) {
+    if let Some(sink) = self.sink.take().and_then(|x| sinks.get(&x)) {
+      sink.stop();
+    }
+    if let Some(source) = source {
+      let settings = PlaybackSettings::LOOP.with_volume(self.volume);
+      let sink = audio.play_with_settings(source.clone(), settings);
+      self.sink = Some(sinks.get_handle(sink));
+    }
+  }
+
+  /// change the volume of the bgm playing and the ones after
+  pub(crate) fn set_volume(&mut self, volume: f32, sinks: &Assets<AudioSink>) {
+    self.volume = volume;
+    if let Some(sink) = self.sink.as_ref().and_then(|x| sinks.get(x)) {
+      sink.set_volume(volume);
+    }
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_image<'w, 's, 'a>(
+  command: &'a mut Commands<'w, 's>,
+  meshes: &mut Assets<Mesh>,
+  materials: &mut Assets<StageMaterial>,
+  component: StageComponent,
+  texture: &Handle<Image>,
+  location: &nahida_core::Location,
+  z_index: f32,
+  animation: Option<Animation>,
+) -> EntityCommands<'w, 's, 'a> {
+  let mut entity = command.spawn((
+    component,
+    OriginalImage(texture.clone()),
+    MaterialMesh2dBundle {
+      mesh: meshes.add(Mesh::from(shape::Cube::new(-1.0))).into(),
+      material: materials.add(StageMaterial::new(texture.clone())),
+      ..Default::default()
+    },
+    Location::new(location.position.clone(), location.size.clone(), z_index),
+  ));
+  if let Some(animation) = animation {
+    entity.insert(ImageAnimation::new(animation));
+  }
+  entity
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn restore_stage(
+  mut command: Commands,
+  mut events: EventReader<RestoreStage>,
+  query: Query<Entity, Or<(With<StageComponent>, With<Outgoing>)>>,
+  resources: Res<NahidaResources>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<StageMaterial>>,
+  audio: Res<Audio>,
+  sinks: Res<Assets<AudioSink>>,
+  mut bgm: ResMut<NahidaBgm>,
+) {
+  let Some(RestoreStage(snapshot)) = events.iter().last() else {
+    return;
+  };
+
+  for entity in query.iter() {
+    command.entity(entity).despawn_recursive();
+  }
+
+  let images = snapshot
+    .bg
+    .iter()
+    .map(|image| (StageComponent::Bg, image))
+    .chain(
+      snapshot
+        .figures
+        .iter()
+        .map(|(name, image)| (StageComponent::Fig(name.clone()), image)),
+    );
+
+  for (z_index, (component, image)) in images.enumerate() {
+    let ImageSnapshot { url, .. } = image;
+    let Some(texture) = resources.image(url) else {
+      error!("Image Not Found: {url:?}");
+      continue;
+    };
+
+    let image = image.to_image();
+    spawn_image(
+      &mut command,
+      &mut meshes,
+      &mut materials,
+      component,
+      texture,
+      &image.location,
+      z_index as f32,
+      image.animation,
+    );
+  }
+
+  let source = snapshot.bgm.as_ref().and_then(|url| {
+    let source = resources.audio(url);
+    if source.is_none() {
+      error!("Audio Not Found: {url:?}");
+    }
+    source
+  });
+  bgm.play(source, &audio, &sinks);
+}
+
+/// whether the transition hides the image instead of showing it
+fn is_hiding(transition: &Option<Transition>) -> bool {
+  matches!(
+    transition.as_ref().map(|x| &x.ty),
+    Some(TransitionType::FadeOut | TransitionType::ConicOut | TransitionType::BlindsOut)
+  )
+}
+
+fn image_transition(
+  transition: &Transition,
+  story: &Path,
+  resources: &NahidaResources,
+) -> ImageTransition {
+  let image_transition = ImageTransition::new(transition.clone());
+  let mask = match &transition.ty {
+    TransitionType::Rule { mask, .. } => join_absolute_path(story, mask),
+    _ => None,
+  };
+
+  match mask.as_deref().and_then(|x| resources.image(x)) {
+    Some(mask) => image_transition.with_mask(mask.clone()),
+    None => image_transition,
+  }
+}
+
+/// play the actions of the step entered on the stage
+#[allow(clippy::too_many_arguments)]
+fn play_stage(
+  mut command: Commands,
+  mut events: EventReader<StepPlayed>,
+  mut images: Query<(Entity, &StageComponent, &mut Location)>,
+  player: Res<NahidaPlayer>,
+  resources: Res<NahidaResources>,
+  stories: Res<Assets<StoryAsset>>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<StageMaterial>>,
+  audio: Res<Audio>,
+  sinks: Res<Assets<AudioSink>>,
+  mut bgm: ResMut<NahidaBgm>,
+  settings: Res<NahidaSettings>,
+) {
+  if events.iter().count() == 0 {
+    return;
+  }
+
+  let loader = StoryAssets {
+    resources: &resources,
+    assets: &stories,
+  };
+  let (Some(cursor), Some(step)) = (player.player.cursor(), player.player.current_step(&loader))
+  else {
+    return;
+  };
+  let resolve = |url: &Path| join_absolute_path(&cursor.path, url).unwrap_or_default();
+
+  let to_transition = |x: &Option<Transition>| {
+    let x = x.as_ref()?;
+    Some(image_transition(x, &cursor.path, &resources))
+  };
+
+  for action in &step.actions {
+    let (component, url, transition, animation, location) = match action {
+      StoryAction::Bg {
+        url,
+        transition,
+        animation,
+        location,
+      } => (StageComponent::Bg, url, transition, animation, location),
+      StoryAction::Fig {
+        name,
+        transition,
+        removal: true,
+        ..
+      } => {
+        let component = StageComponent::Fig(name.clone());
+        let keep = transition.is_some();
+        leave(
+          &mut command,
+          &mut images,
+          &component,
+          to_transition(transition),
+          keep,
+        );
+        continue;
+      }
+      StoryAction::Fig {
+        name,
+        url,
+        transition,
+        animation,
+        location,
+        ..
+      } => (
+        StageComponent::Fig(name.clone()),
+        url,
+        transition,
+        animation,
+        location,
+      ),
+      StoryAction::Bgm { url } => {
+        bgm.play(resources.audio(&resolve(url)), &audio, &sinks);
+        continue;
+      }
+      StoryAction::Sfx { url } => {
+        match resources.audio(&resolve(url)) {
+          Some(source) => {
+            let volume = settings.volume(AudioChannel::Sfx);
+            audio.play_with_settings(source.clone(), PlaybackSettings::ONCE.with_volume(volume));
+          }
+          None => error!("Audio Not Found: {url:?}"),
+        }
+        continue;
+      }
+      _ => continue,
+    };
+
+    let url = resolve(url);
+    let Some(texture) = resources.image(&url) else {
+      error!("Image Not Found: {url:?}");
+      continue;
+    };
+
+    // figures go above the background in the order they come on
+    let z_index = match &component {
+      StageComponent::Bg => 0.0,
+      StageComponent::Fig(name) => {
+        let figures = &player.player.stage().figures;
+        1.0 + figures.iter().position(|(x, _)| x == name).unwrap_or(0) as f32
+      }
+    };
+
+    leave(
+      &mut command,
+      &mut images,
+      &component,
+      None,
+      !is_hiding(transition),
+    );
+    let mut entity = spawn_image(
+      &mut command,
+      &mut meshes,
+      &mut materials,
+      component,
+      texture,
+      location,
+      z_index,
+      animation.clone(),
+    );
+    if let Some(transition) = to_transition(transition) {
+      entity.insert(transition);
+    }
+  }
+}
+
+/// the old image goes below the new one until the transitions are over,
+/// or goes away right now if it is not kept
+fn leave(
+  command: &mut Commands,
+  images: &mut Query<(Entity, &StageComponent, &mut Location)>,
+  target: &StageComponent,
+  transition: Option<ImageTransition>,
+  keep: bool,
+) {
+  for (entity, _, mut location) in images.iter_mut().filter(|(_, x, _)| *x == target) {
+    let mut entity = command.entity(entity);
+    if !keep {
+      entity.despawn_recursive();
+      continue;
+    }
+
+    location.z_index -= 0.5;
+    entity.remove::<StageComponent>().insert(Outgoing);
+    if let Some(transition) = &transition {
+      entity.insert(transition.clone());
+    }
+  }
+}
+
+/// remove the images left behind once every transition is over
+fn despawn_outgoing(
+  mut command: Commands,
+  outgoing: Query<Entity, With<Outgoing>>,
+  transitions: Query<&ImageTransition>,
+) {
+  if transitions.iter().any(|x| !x.finished()) {
+    return;
+  }
+
+  for entity in outgoing.iter() {
+    command.entity(entity).despawn_recursive();
+  }
+}
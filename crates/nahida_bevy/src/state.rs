@@ -1,15 +1,17 @@
 use bevy::prelude::*;
 
-use self::{loading::LoadingPlugin, menu::MenuPlugin};
+use self::{loading::LoadingPlugin, menu::MenuPlugin, playing::PlayingPlugin};
 
 pub mod loading;
 pub mod menu;
+pub mod playing;
 
 #[derive(States, Default, Debug, Hash, Clone, PartialEq, Eq)]
 pub enum NahidaState {
   #[default]
   Loading,
   Menu,
+  Playing,
 }
 
 pub struct NahidaStatePlugin;
@@ -19,6 +21,7 @@ impl Plugin for NahidaStatePlugin {
     app
       .add_state::<NahidaState>()
       .add_plugin(LoadingPlugin)
-      .add_plugin(MenuPlugin);
+      .add_plugin(MenuPlugin)
+      .add_plugin(PlayingPlugin);
   }
 }
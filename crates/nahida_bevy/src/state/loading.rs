@@ -1,62 +1,39 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+};
 
 use bevy::prelude::*;
-use nahida_core::story::StoryAction;
+use nahida_core::{
+  path::join_absolute_path,
+  story::{StoryAction, StoryStep, Transition, TransitionType},
+};
 
 use crate::{asset::story::StoryAsset, NahidaEntryPoint};
 
 use super::NahidaState;
 
-fn join_absolute_path(src: &PathBuf, dst: &PathBuf) -> Option<PathBuf> {
-  let mut parent = src.parent();
-  let mut dst = dst.as_path();
-
-  loop {
-    match () {
-      _ if dst.starts_with("./") => {
-        dst = dst.strip_prefix("./").unwrap();
-      }
-      _ if dst.starts_with("../") => {
-        dst = dst.strip_prefix("../").unwrap();
-        parent = parent.and_then(|p| p.parent());
+/// collect actions from steps, including the branches of choices
+fn collect_actions<'a>(steps: &'a [StoryStep], actions: &mut Vec<&'a StoryAction>) {
+  for step in steps {
+    for action in &step.actions {
+      if let StoryAction::Choice { options } = action {
+        for option in options {
+          collect_actions(&option.steps, actions);
+        }
       }
-      _ => break,
+      actions.push(action);
     }
   }
-
-  parent.and_then(|p| Some(p.join(dst)))
 }
 
-#[test]
-fn test_join_absolute_path() {
-  use std::path::PathBuf;
-
-  let a = PathBuf::from("index.md");
-  let b = PathBuf::from("story/chapter1/scene1.md");
-  let c = PathBuf::from("./bad_end.md");
-  let d = PathBuf::from("../../happy_end.md");
-
-  assert_eq!(
-    join_absolute_path(&a, &b),
-    Some(PathBuf::from("story/chapter1/scene1.md"))
-  );
-  assert_eq!(
-    join_absolute_path(&a, &c),
-    Some(PathBuf::from("bad_end.md"))
-  );
-  assert_eq!(join_absolute_path(&a, &d), None);
-  assert_eq!(
-    join_absolute_path(&b, &a),
-    Some(PathBuf::from("story/chapter1/index.md"))
-  );
-  assert_eq!(
-    join_absolute_path(&b, &c),
-    Some(PathBuf::from("story/chapter1/bad_end.md"))
-  );
-  assert_eq!(
-    join_absolute_path(&b, &d),
-    Some(PathBuf::from("happy_end.md"))
-  );
+/// audio played by the action, including the voice and the sfx cued in a text
+fn audio_urls(action: &StoryAction) -> Vec<&PathBuf> {
+  match action {
+    StoryAction::Bgm { url } | StoryAction::Sfx { url } => vec![url],
+    StoryAction::Text { text, voice, .. } => voice.iter().chain(text.sfx()).collect(),
+    _ => Vec::new(),
+  }
 }
 
 pub struct LoadingPlugin;
@@ -157,6 +134,24 @@ pub struct NahidaResources {
   audio: HashMap<PathBuf, Handle<AudioSource>>,
 }
 
+impl NahidaResources {
+  pub fn story(&self, path: &Path) -> Option<&Handle<StoryAsset>> {
+    self.story.get(path)
+  }
+
+  pub fn insert_story(&mut self, path: PathBuf, handle: Handle<StoryAsset>) {
+    self.story.insert(path, handle);
+  }
+
+  pub fn image(&self, path: &Path) -> Option<&Handle<Image>> {
+    self.image.get(path)
+  }
+
+  pub fn audio(&self, path: &Path) -> Option<&Handle<AudioSource>> {
+    self.audio.get(path)
+  }
+}
+
 fn setup_load_entry_point(
   entry_point: Res<NahidaEntryPoint>,
   mut loading_state: ResMut<NahidaLoadingState>,
@@ -167,7 +162,7 @@ fn setup_load_entry_point(
   loading_state.logs.push(format!("Loading: {url:?}"));
   info!("Loading: {url:?}");
   let story = asset_server.load(url.clone());
-  loaded_resource.story.insert(url.clone(), story);
+  loaded_resource.insert_story(url.clone(), story);
   loading_state.queue.push(url.clone());
 }
 
@@ -188,10 +183,27 @@ fn load_resource_recursive(
 
     if let Some(story) = asset {
       // find assets recursive
-      for step in &story.story.steps {
-        for action in &step.actions {
-          match action {
-            StoryAction::Bg { url, .. } | StoryAction::Fig { url, .. } => {
+      let mut actions = Vec::new();
+      collect_actions(&story.story.steps, &mut actions);
+
+      for action in actions {
+        match action {
+          StoryAction::Bg {
+            url, transition, ..
+          }
+          | StoryAction::Fig {
+            url, transition, ..
+          } => {
+            // the mask of a rule transition is an image as well
+            let mask = match transition {
+              Some(Transition {
+                ty: TransitionType::Rule { mask, .. },
+                ..
+              }) => Some(mask),
+              _ => None,
+            };
+
+            for url in std::iter::once(url).chain(mask) {
               let url = join_absolute_path(src, url);
               match url {
                 Some(url) if !loaded_resource.image.contains_key(&url) => {
@@ -209,7 +221,9 @@ fn load_resource_recursive(
                 }
               }
             }
-            StoryAction::Bgm { url } | StoryAction::Sfx { url } => {
+          }
+          StoryAction::Bgm { .. } | StoryAction::Sfx { .. } | StoryAction::Text { .. } => {
+            for url in audio_urls(action) {
               let url = join_absolute_path(src, url);
               match url {
                 Some(url) if !loaded_resource.audio.contains_key(&url) => {
@@ -227,27 +241,27 @@ fn load_resource_recursive(
                 }
               }
             }
-            StoryAction::Navigate { url, .. } => {
-              let url = join_absolute_path(src, url);
-              match url {
-                Some(url) if !loaded_resource.story.contains_key(&url) => {
-                  logs.push(format!("Loading: {url:?}"));
-                  info!("Loading: {url:?}");
-                  let story = asset_server.load(url.clone());
-                  inserts.push(url.clone());
-                  loaded_resource.story.insert(url, story);
-                }
-                Some(_) => {
-                  // already loaded, skipping
-                }
-                None => {
-                  logs.push(format!("Error: Route Not found: {url:?}"));
-                  error!("Route Not Found: {url:?}");
-                }
+          }
+          StoryAction::Navigate { url, .. } => {
+            let url = join_absolute_path(src, url);
+            match url {
+              Some(url) if !loaded_resource.story.contains_key(&url) => {
+                logs.push(format!("Loading: {url:?}"));
+                info!("Loading: {url:?}");
+                let story = asset_server.load(url.clone());
+                inserts.push(url.clone());
+                loaded_resource.insert_story(url, story);
+              }
+              Some(_) => {
+                // already loaded, skipping
+              }
+              None => {
+                logs.push(format!("Error: Route Not found: {url:?}"));
+                error!("Route Not Found: {url:?}");
               }
             }
-            _ => {}
           }
+          _ => {}
         }
       }
     }
@@ -1,68 +1,445 @@
-use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
-use nahida_core::{Position, Size};
+use bevy::{app::AppExit, prelude::*, sprite::MaterialMesh2dBundle, ui::FocusPolicy};
+use nahida_core::Position;
 
 use crate::{
-  material::menu::background::MenuBackgroundMaterial, Location, OriginalImage, WindowSize,
+  asset::story::StoryAsset,
+  backlog::Backlog,
+  material::menu::background::MenuBackgroundMaterial,
+  player::{NahidaPlayer, StoryAssets},
+  save::{format_timestamp, SaveError, SaveSlot, SaveStorage},
+  stage::NahidaBgm,
+  Location, OriginalImage,
 };
 
-use super::{loading::NahidaFonts, NahidaState};
+use super::{
+  loading::{NahidaFonts, NahidaResources},
+  NahidaState,
+};
 
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
   fn build(&self, app: &mut App) {
-    app.add_system(setup_menu.in_schedule(OnEnter(NahidaState::Menu)));
+    app
+      .init_resource::<NahidaMenuConfig>()
+      .add_event::<OpenSettings>()
+      .add_system(setup_menu.in_schedule(OnEnter(NahidaState::Menu)))
+      .add_systems(
+        (
+          click_menu_button,
+          click_load_button,
+          close_load_screen,
+          highlight_buttons,
+        )
+          .in_set(OnUpdate(NahidaState::Menu)),
+      )
+      .add_system(destroy_menu.in_schedule(OnExit(NahidaState::Menu)));
   }
 }
 
+/// what a button of the title screen does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+  /// start over from the entry point
+  Start,
+  /// load the save written last
+  Continue,
+  /// pick a save to load
+  Load,
+  Settings,
+  Quit,
+}
+
+/// the title screen, set by the project before adding the plugin
+#[derive(Resource, Clone)]
+pub struct NahidaMenuConfig {
+  /// paths in the assets folder
+  pub background: Option<String>,
+  pub logo: Option<String>,
+  pub bgm: Option<String>,
+  /// shown in place of the logo
+  pub title: Option<String>,
+  /// labels and actions of the buttons, from top to bottom
+  pub buttons: Vec<(String, MenuAction)>,
+  /// the column holding the buttons
+  pub buttons_style: Style,
+  pub background_color: Color,
+}
+
+impl Default for NahidaMenuConfig {
+  fn default() -> Self {
+    Self {
+      background: None,
+      logo: None,
+      bgm: None,
+      title: None,
+      buttons: vec![
+        ("开始游戏".into(), MenuAction::Start),
+        ("继续游戏".into(), MenuAction::Continue),
+        ("读取存档".into(), MenuAction::Load),
+        ("设置".into(), MenuAction::Settings),
+        ("退出".into(), MenuAction::Quit),
+      ],
+      buttons_style: Style {
+        position_type: PositionType::Absolute,
+        position: UiRect {
+          left: Val::Percent(10.0),
+          bottom: Val::Percent(15.0),
+          ..Default::default()
+        },
+        flex_direction: FlexDirection::Column,
+        ..Default::default()
+      },
+      background_color: Color::BLACK,
+    }
+  }
+}
+
+/// sent when the settings are asked for
+pub struct OpenSettings;
+
 #[derive(Component)]
 pub struct MenuComponent;
 
+#[derive(Component)]
+struct MenuButton(MenuAction);
+
+/// the saves to pick from, covering the title screen while it is open
+#[derive(Component)]
+struct LoadScreen;
+
+#[derive(Component)]
+struct LoadButton(SaveSlot);
+
+pub(crate) const BUTTON_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+pub(crate) const HOVERED_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.2);
+const DISABLED_COLOR: Color = Color::rgba(0.5, 0.5, 0.5, 1.0);
+
+pub(crate) fn button_style(fonts: &NahidaFonts) -> TextStyle {
+  TextStyle {
+    font: fonts.hanyi.clone(),
+    font_size: 28.0,
+    color: Color::WHITE,
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn setup_menu(
   mut command: Commands,
+  config: Res<NahidaMenuConfig>,
   fonts: Res<NahidaFonts>,
+  storage: Option<Res<SaveStorage>>,
   mut color: ResMut<ClearColor>,
   mut meshes: ResMut<Assets<Mesh>>,
   mut material: ResMut<Assets<MenuBackgroundMaterial>>,
   asset_server: Res<AssetServer>,
-  window_size: Res<WindowSize>,
+  audio: Res<Audio>,
+  sinks: Res<Assets<AudioSink>>,
+  mut bgm: ResMut<NahidaBgm>,
 ) {
-  color.0 = Color::ALICE_BLUE;
-
-  command.spawn(
-    TextBundle::from_section(
-      "This is menu",
-      TextStyle {
-        font: fonts.hanyi.clone(),
-        font_size: 24.0,
-        color: Color::BLACK,
+  color.0 = config.background_color;
+
+  if let Some(background) = &config.background {
+    let texture = asset_server.load(background.as_str());
+    command.spawn((
+      MenuComponent,
+      OriginalImage(texture.clone()),
+      MaterialMesh2dBundle {
+        mesh: meshes.add(Mesh::from(shape::Cube::new(-1.0))).into(),
+        material: material.add(MenuBackgroundMaterial {
+          time: 0.0,
+          texture: Some(texture),
+        }),
+        ..Default::default()
       },
-    )
-    .with_style(Style {
-      position_type: PositionType::Absolute,
-      position: UiRect {
-        left: Val::Px(20.0),
-        top: Val::Px(20.0),
+      Location::new(Position(0.5, 0.5), nahida_core::Size::Cover, 0.0),
+    ));
+  }
+
+  let source = config.bgm.as_ref().map(|x| asset_server.load(x.as_str()));
+  bgm.play(source.as_ref(), &audio, &sinks);
+
+  let has_save = storage.is_some_and(|x| !x.slots().is_empty());
+  let style = button_style(&fonts);
+
+  command
+    .spawn((
+      MenuComponent,
+      NodeBundle {
+        style: Style {
+          position_type: PositionType::Absolute,
+          size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+          ..Default::default()
+        },
+        ..Default::default()
+      },
+    ))
+    .with_children(|screen| {
+      let title = Style {
+        position_type: PositionType::Absolute,
+        position: UiRect {
+          left: Val::Percent(10.0),
+          top: Val::Percent(15.0),
+          ..Default::default()
+        },
+        ..Default::default()
+      };
+      match (&config.logo, &config.title) {
+        (Some(logo), _) => {
+          screen.spawn(ImageBundle {
+            image: asset_server.load(logo.as_str()).into(),
+            style: Style {
+              max_size: Size::new(Val::Percent(40.0), Val::Percent(30.0)),
+              ..title
+            },
+            ..Default::default()
+          });
+        }
+        (None, Some(text)) => {
+          screen.spawn(
+            TextBundle::from_section(
+              text.clone(),
+              TextStyle {
+                font_size: 64.0,
+                ..style.clone()
+              },
+            )
+            .with_style(title),
+          );
+        }
+        (None, None) => {}
+      }
+
+      screen
+        .spawn(NodeBundle {
+          style: config.buttons_style.clone(),
+          ..Default::default()
+        })
+        .with_children(|column| {
+          for (label, action) in &config.buttons {
+            // nothing to continue or load before the first save
+            let enabled = has_save || !matches!(action, MenuAction::Continue | MenuAction::Load);
+            let mut button = column.spawn(ButtonBundle {
+              style: Style {
+                margin: UiRect::vertical(Val::Px(8.0)),
+                padding: UiRect::new(Val::Px(24.0), Val::Px(24.0), Val::Px(8.0), Val::Px(8.0)),
+                ..Default::default()
+              },
+              background_color: BUTTON_COLOR.into(),
+              ..Default::default()
+            });
+            if enabled {
+              button.insert(MenuButton(*action));
+            }
+
+            let color = match enabled {
+              true => style.color,
+              false => DISABLED_COLOR,
+            };
+            button.with_children(|button| {
+              button.spawn(TextBundle::from_section(
+                label.clone(),
+                TextStyle {
+                  color,
+                  ..style.clone()
+                },
+              ));
+            });
+          }
+        });
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn click_menu_button(
+  mut command: Commands,
+  buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+  mut player: ResMut<NahidaPlayer>,
+  mut backlog: ResMut<Backlog>,
+  storage: Option<Res<SaveStorage>>,
+  fonts: Res<NahidaFonts>,
+  resources: Res<NahidaResources>,
+  stories: Res<Assets<StoryAsset>>,
+  mut next_state: ResMut<NextState<NahidaState>>,
+  mut settings: EventWriter<OpenSettings>,
+  mut exit: EventWriter<AppExit>,
+) {
+  let loader = StoryAssets {
+    resources: &resources,
+    assets: &stories,
+  };
+
+  for (interaction, MenuButton(action)) in buttons.iter() {
+    if *interaction != Interaction::Clicked {
+      continue;
+    }
+
+    match action {
+      MenuAction::Start => {
+        player.restart();
+        backlog.clear();
+        next_state.set(NahidaState::Playing);
+      }
+      MenuAction::Continue => {
+        let Some((slot, data)) = storage.as_ref().and_then(|x| x.latest()) else {
+          continue;
+        };
+        match player.load(data, &loader) {
+          Ok(_) => {
+            backlog.clear();
+            next_state.set(NahidaState::Playing);
+          }
+          Err(e) => error!("Failed to load {slot:?}: {e}"),
+        }
+      }
+      MenuAction::Load => spawn_load_screen(&mut command, storage.as_deref(), &fonts),
+      MenuAction::Settings => settings.send(OpenSettings),
+      MenuAction::Quit => exit.send(AppExit),
+    }
+  }
+}
+
+fn spawn_load_screen(command: &mut Commands, storage: Option<&SaveStorage>, fonts: &NahidaFonts) {
+  let style = button_style(fonts);
+
+  command
+    .spawn((
+      MenuComponent,
+      LoadScreen,
+      NodeBundle {
+        style: Style {
+          position_type: PositionType::Absolute,
+          size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+          flex_direction: FlexDirection::Column,
+          align_items: AlignItems::Center,
+          justify_content: JustifyContent::Center,
+          ..Default::default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+        focus_policy: FocusPolicy::Block,
+        z_index: ZIndex::Global(10),
         ..Default::default()
       },
-      ..Default::default()
-    }),
-  );
-
-  println!("window: {window_size:?}");
-
-  let tree = asset_server.load("tree.png");
-
-  command.spawn((
-    OriginalImage(tree.clone()),
-    MaterialMesh2dBundle {
-      mesh: meshes.add(Mesh::from(shape::Cube::new(-1.0))).into(),
-      material: material.add(MenuBackgroundMaterial {
-        time: 0.0,
-        texture: Some(tree),
-      }),
-      ..Default::default()
-    },
-    Location::new(Position(0.5, 0.5), Size::Contain, 0.0),
-  ));
+    ))
+    .with_children(|screen| {
+      let Some(storage) = storage else {
+        return;
+      };
+
+      for slot in storage.slots() {
+        let Ok(data) = storage.load(slot) else {
+          continue;
+        };
+        let name = match slot {
+          SaveSlot::Quick => "快速存档".to_string(),
+          SaveSlot::Numbered(n) => format!("存档 {n}"),
+        };
+
+        screen
+          .spawn((
+            LoadButton(slot),
+            ButtonBundle {
+              style: Style {
+                size: Size::width(Val::Px(480.0)),
+                margin: UiRect::vertical(Val::Px(8.0)),
+                padding: UiRect::all(Val::Px(12.0)),
+                justify_content: JustifyContent::SpaceBetween,
+                ..Default::default()
+              },
+              background_color: BUTTON_COLOR.into(),
+              ..Default::default()
+            },
+          ))
+          .with_children(|button| {
+            button.spawn(TextBundle::from_section(name, style.clone()));
+            button.spawn(TextBundle::from_section(
+              format_timestamp(data.timestamp),
+              style.clone(),
+            ));
+          });
+      }
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn click_load_button(
+  buttons: Query<(&Interaction, &LoadButton), Changed<Interaction>>,
+  mut player: ResMut<NahidaPlayer>,
+  mut backlog: ResMut<Backlog>,
+  storage: Option<Res<SaveStorage>>,
+  resources: Res<NahidaResources>,
+  stories: Res<Assets<StoryAsset>>,
+  mut next_state: ResMut<NextState<NahidaState>>,
+) {
+  let loader = StoryAssets {
+    resources: &resources,
+    assets: &stories,
+  };
+
+  for (interaction, LoadButton(slot)) in buttons.iter() {
+    if *interaction != Interaction::Clicked {
+      continue;
+    }
+
+    match storage
+      .as_deref()
+      .ok_or(SaveError::NoStorage)
+      .and_then(|x| x.load(*slot))
+      .and_then(|data| player.load(data, &loader))
+    {
+      Ok(_) => {
+        backlog.clear();
+        next_state.set(NahidaState::Playing);
+      }
+      Err(e) => error!("Failed to load {slot:?}: {e}"),
+    }
+  }
+}
+
+/// `Esc` or right click goes back to the title screen
+fn close_load_screen(
+  mut command: Commands,
+  keys: Res<Input<KeyCode>>,
+  buttons: Res<Input<MouseButton>>,
+  screens: Query<Entity, With<LoadScreen>>,
+) {
+  if !(keys.just_pressed(KeyCode::Escape) || buttons.just_pressed(MouseButton::Right)) {
+    return;
+  }
+
+  for entity in screens.iter() {
+    command.entity(entity).despawn_recursive();
+  }
+}
+
+#[allow(clippy::type_complexity)]
+fn highlight_buttons(
+  mut buttons: Query<
+    (&Interaction, &mut BackgroundColor),
+    (
+      Changed<Interaction>,
+      Or<(With<MenuButton>, With<LoadButton>)>,
+    ),
+  >,
+) {
+  for (interaction, mut color) in buttons.iter_mut() {
+    *color = match interaction {
+      Interaction::None => BUTTON_COLOR.into(),
+      _ => HOVERED_COLOR.into(),
+    };
+  }
+}
+
+fn destroy_menu(
+  mut command: Commands,
+  query: Query<Entity, With<MenuComponent>>,
+  audio: Res<Audio>,
+  sinks: Res<Assets<AudioSink>>,
+  mut bgm: ResMut<NahidaBgm>,
+) {
+  for entity in query.iter() {
+    command.entity(entity).despawn_recursive();
+  }
+
+  bgm.play(None, &audio, &sinks);
 }
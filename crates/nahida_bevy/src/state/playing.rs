@@ -0,0 +1,777 @@
+use std::{path::Path, time::Duration};
+
+use bevy::{prelude::*, ui::FocusPolicy};
+use nahida_core::{
+  path::join_absolute_path,
+  story::StoryAction,
+  text::{RichText, TextSpan},
+};
+
+use crate::{
+  asset::story::StoryAsset,
+  backlog::BacklogScreen,
+  mode::PlayMode,
+  player::{
+    AdvanceRequest, AdvanceSet, ChooseRequest, NahidaPlayer, StepPlayed, StoryAssets, StoryFinished,
+  },
+  save::{format_timestamp, SaveRequest, SaveSlot, SaveStorage},
+  settings::{AudioChannel, NahidaSettings},
+  stage::{NahidaBgm, Outgoing, RestoreStage, StageComponent},
+  text::{rich_text_sections, Typewriter},
+  ImageTransition,
+};
+
+use super::{
+  loading::{NahidaFonts, NahidaResources},
+  menu::{button_style, BUTTON_COLOR, HOVERED_COLOR},
+  NahidaState,
+};
+
+pub struct PlayingPlugin;
+
+impl Plugin for PlayingPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<PendingStep>()
+      .init_resource::<NahidaVoice>()
+      .add_system(setup_playing.in_schedule(OnEnter(NahidaState::Playing)))
+      .add_systems(
+        (
+          advance_on_input.before(AdvanceSet),
+          choose_option.before(AdvanceSet),
+          continue_step.before(AdvanceSet),
+          show_step.after(AdvanceSet),
+          type_text.after(show_step),
+          finish_story.after(AdvanceSet),
+        )
+          .in_set(OnUpdate(NahidaState::Playing)),
+      )
+      .add_systems(
+        (
+          open_save_screen,
+          click_save_button,
+          close_save_screen,
+          highlight_save_buttons,
+        )
+          .in_set(OnUpdate(NahidaState::Playing)),
+      )
+      .add_system(destroy_playing.in_schedule(OnExit(NahidaState::Playing)));
+  }
+}
+
+#[derive(Component)]
+pub struct PlayingComponent;
+
+/// the speaker above the dialogue box
+#[derive(Component)]
+struct DialogueName;
+
+#[derive(Component)]
+struct DialogueText;
+
+/// the options of a choice, covering the stage until one is picked
+#[derive(Component)]
+struct ChoiceComponent;
+
+#[derive(Component)]
+struct ChoiceButton(usize);
+
+/// the slots to save to, covering the stage while it is open
+#[derive(Component)]
+pub struct SaveScreen;
+
+#[derive(Component)]
+struct SaveButton(u32);
+
+/// numbered slots shown in the save screen
+const SAVE_SLOTS: u32 = 6;
+
+/// screens over the stage, taking the input while they are open
+pub(crate) type Overlays = Or<(With<BacklogScreen>, With<SaveScreen>)>;
+
+/// waits of the current step, steps without text or choice go on by themselves
+#[derive(Resource, Default)]
+struct PendingStep {
+  wait: Duration,
+  elapsed: Duration,
+  auto: bool,
+}
+
+impl PendingStep {
+  fn waiting(&self) -> bool {
+    self.elapsed < self.wait
+  }
+}
+
+/// the voice of the current step, stopped when the next step comes
+#[derive(Resource, Default)]
+pub(crate) struct NahidaVoice(pub(crate) Option<Handle<AudioSink>>);
+
+fn setup_playing(
+  mut command: Commands,
+  fonts: Res<NahidaFonts>,
+  player: Res<NahidaPlayer>,
+  mut color: ResMut<ClearColor>,
+  mut advance: EventWriter<AdvanceRequest>,
+  mut restore: EventWriter<RestoreStage>,
+) {
+  color.0 = Color::BLACK;
+
+  command
+    .spawn((
+      PlayingComponent,
+      NodeBundle {
+        style: Style {
+          position_type: PositionType::Absolute,
+          position: UiRect {
+            left: Val::Px(0.0),
+            right: Val::Px(0.0),
+            bottom: Val::Px(0.0),
+            ..Default::default()
+          },
+          size: Size::height(Val::Percent(30.0)),
+          flex_direction: FlexDirection::Column,
+          padding: UiRect::new(Val::Px(60.0), Val::Px(60.0), Val::Px(24.0), Val::Px(24.0)),
+          ..Default::default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+        ..Default::default()
+      },
+    ))
+    .with_children(|dialogue| {
+      dialogue.spawn((
+        DialogueName,
+        TextBundle::from_section(
+          "",
+          TextStyle {
+            font: fonts.hanyi.clone(),
+            font_size: 28.0,
+            color: Color::GOLD,
+          },
+        )
+        .with_style(Style {
+          margin: UiRect::bottom(Val::Px(12.0)),
+          ..Default::default()
+        }),
+      ));
+      dialogue.spawn((
+        DialogueText,
+        Typewriter::default(),
+        TextBundle::default().with_style(Style {
+          max_size: Size::width(Val::Percent(100.0)),
+          ..Default::default()
+        }),
+      ));
+    });
+
+  // a new game starts from the first step, a loaded one from where it was
+  match player.player.cursor() {
+    Some(_) => restore.send(RestoreStage(player.player.stage().snapshot())),
+    None => advance.send(AdvanceRequest),
+  }
+}
+
+fn dialogue_style(fonts: &NahidaFonts) -> TextStyle {
+  TextStyle {
+    font: fonts.hanyi.clone(),
+    font_size: 24.0,
+    color: Color::WHITE,
+  }
+}
+
+/// show the text and the choice of the current step
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn show_step(
+  mut command: Commands,
+  mut played: EventReader<StepPlayed>,
+  mut restored: EventReader<RestoreStage>,
+  mut names: Query<&mut Text, (With<DialogueName>, Without<DialogueText>)>,
+  mut texts: Query<(&mut Text, &mut Typewriter), (With<DialogueText>, Without<DialogueName>)>,
+  choices: Query<Entity, With<ChoiceComponent>>,
+  player: Res<NahidaPlayer>,
+  resources: Res<NahidaResources>,
+  stories: Res<Assets<StoryAsset>>,
+  fonts: Res<NahidaFonts>,
+  audio: Res<Audio>,
+  sinks: Res<Assets<AudioSink>>,
+  mut voice: ResMut<NahidaVoice>,
+  mut pending: ResMut<PendingStep>,
+  settings: Res<NahidaSettings>,
+) {
+  // the voice is only played when the step is entered, not when restored
+  let played = played.iter().count() > 0;
+  if !played && restored.iter().count() == 0 {
+    return;
+  }
+
+  let loader = StoryAssets {
+    resources: &resources,
+    assets: &stories,
+  };
+  let (Some(cursor), Some(step)) = (player.player.cursor(), player.player.current_step(&loader))
+  else {
+    return;
+  };
+
+  if let Some(sink) = voice.0.take().and_then(|x| sinks.get(&x)) {
+    sink.stop();
+  }
+  for entity in choices.iter() {
+    command.entity(entity).despawn_recursive();
+  }
+
+  let mut name = String::new();
+  let mut typewriter = Typewriter::default();
+  let mut wait = Duration::ZERO;
+  let mut auto = true;
+
+  for action in &step.actions {
+    match action {
+      StoryAction::Text {
+        name: speaker,
+        text,
+        voice: url,
+      } => {
+        auto = false;
+        name = speaker.clone().unwrap_or_default();
+        typewriter = Typewriter::new(
+          resolve_cues(text, &cursor.path),
+          player.player.config().text_speed,
+        );
+
+        let url = url
+          .as_ref()
+          .and_then(|x| join_absolute_path(&cursor.path, x));
+        match url.as_deref().map(|x| (x, resources.audio(x))) {
+          Some((_, Some(source))) if played => {
+            let volume = settings.volume(AudioChannel::Voice);
+            let sink =
+              audio.play_with_settings(source.clone(), PlaybackSettings::ONCE.with_volume(volume));
+            voice.0 = Some(sinks.get_handle(sink));
+          }
+          Some((url, None)) => error!("Audio Not Found: {url:?}"),
+          _ => {}
+        }
+      }
+      StoryAction::Wait { time } => wait += *time,
+      StoryAction::Choice { options } => {
+        auto = false;
+        spawn_choice(&mut command, options.iter().map(|x| &x.text), &fonts);
+      }
+      _ => {}
+    }
+  }
+
+  for mut text in names.iter_mut() {
+    text.sections[0].value = name.clone();
+  }
+  // a restored step is shown as it was left
+  if !played {
+    typewriter.complete();
+  }
+  for (mut text, mut current) in texts.iter_mut() {
+    text.sections.clear();
+    *current = std::mem::take(&mut typewriter);
+  }
+
+  *pending = PendingStep {
+    wait,
+    elapsed: Duration::ZERO,
+    auto,
+  };
+}
+
+/// the sfx cued in the text, relative to the story
+fn resolve_cues(text: &RichText, story: &Path) -> RichText {
+  let spans = text.spans.iter().map(|span| match span {
+    TextSpan::Sfx(url) => TextSpan::Sfx(join_absolute_path(story, url).unwrap_or_default()),
+    span => span.clone(),
+  });
+  RichText::new(spans.collect())
+}
+
+fn type_text(
+  mut texts: Query<(&mut Text, &mut Typewriter)>,
+  fonts: Res<NahidaFonts>,
+  resources: Res<NahidaResources>,
+  audio: Res<Audio>,
+  settings: Res<NahidaSettings>,
+  time: Res<Time>,
+) {
+  let volume = settings.volume(AudioChannel::Sfx);
+
+  for (mut text, mut typewriter) in texts.iter_mut() {
+    if !typewriter.tick(time.delta()) {
+      continue;
+    }
+
+    text.sections = rich_text_sections(typewriter.typed(), &dialogue_style(&fonts));
+    for url in typewriter.take_cues() {
+      match resources.audio(&url) {
+        Some(source) => {
+          audio.play_with_settings(source.clone(), PlaybackSettings::ONCE.with_volume(volume));
+        }
+        None => error!("Audio Not Found: {url:?}"),
+      }
+    }
+  }
+}
+
+fn spawn_choice<'a>(
+  command: &mut Commands,
+  options: impl Iterator<Item = &'a String>,
+  fonts: &NahidaFonts,
+) {
+  command
+    .spawn((
+      PlayingComponent,
+      ChoiceComponent,
+      NodeBundle {
+        style: Style {
+          position_type: PositionType::Absolute,
+          size: Size::new(Val::Percent(100.0), Val::Percent(70.0)),
+          flex_direction: FlexDirection::Column,
+          justify_content: JustifyContent::Center,
+          align_items: AlignItems::Center,
+          ..Default::default()
+        },
+        ..Default::default()
+      },
+    ))
+    .with_children(|choice| {
+      for (index, option) in options.enumerate() {
+        choice
+          .spawn((
+            ChoiceButton(index),
+            ButtonBundle {
+              style: Style {
+                size: Size::width(Val::Percent(50.0)),
+                margin: UiRect::all(Val::Px(8.0)),
+                padding: UiRect::all(Val::Px(12.0)),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+              },
+              background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+              ..Default::default()
+            },
+          ))
+          .with_children(|button| {
+            button.spawn(TextBundle::from_section(
+              option.clone(),
+              dialogue_style(fonts),
+            ));
+          });
+      }
+    });
+}
+
+/// go on after the waits of the steps without text
+fn continue_step(
+  mut pending: ResMut<PendingStep>,
+  transitions: Query<&ImageTransition>,
+  time: Res<Time>,
+  mut advance: EventWriter<AdvanceRequest>,
+) {
+  if !pending.auto {
+    return;
+  }
+
+  pending.elapsed += time.delta();
+  if !pending.waiting() && transitions.iter().all(|x| x.finished()) {
+    pending.auto = false;
+    advance.send(AdvanceRequest);
+  }
+}
+
+/// click, `Space` or `Enter` finishes the transitions, the typing and the wait first,
+/// then goes to the next step
+#[allow(clippy::too_many_arguments)]
+fn advance_on_input(
+  keys: Res<Input<KeyCode>>,
+  buttons: Res<Input<MouseButton>>,
+  overlays: Query<(), Overlays>,
+  choices: Query<(), With<ChoiceComponent>>,
+  mut transitions: Query<&mut ImageTransition>,
+  mut typewriters: Query<&mut Typewriter>,
+  mut pending: ResMut<PendingStep>,
+  mut mode: ResMut<PlayMode>,
+  mut advance: EventWriter<AdvanceRequest>,
+) {
+  let pressed = buttons.just_pressed(MouseButton::Left)
+    || keys.any_just_pressed([KeyCode::Space, KeyCode::Return]);
+  if !pressed || !overlays.is_empty() || !choices.is_empty() {
+    return;
+  }
+
+  if *mode == PlayMode::Skip {
+    *mode = PlayMode::Normal;
+    return;
+  }
+
+  let mut skipped = false;
+  for mut transition in transitions.iter_mut().filter(|x| !x.finished()) {
+    transition.skip();
+    skipped = true;
+  }
+  for mut typewriter in typewriters.iter_mut().filter(|x| !x.finished()) {
+    typewriter.complete();
+    skipped = true;
+  }
+
+  if skipped {
+    return;
+  }
+  if pending.waiting() {
+    pending.elapsed = pending.wait;
+    return;
+  }
+  advance.send(AdvanceRequest);
+}
+
+fn choose_option(
+  buttons: Query<(&Interaction, &ChoiceButton), Changed<Interaction>>,
+  mut choose: EventWriter<ChooseRequest>,
+) {
+  for (interaction, ChoiceButton(index)) in buttons.iter() {
+    if *interaction == Interaction::Clicked {
+      choose.send(ChooseRequest(*index));
+    }
+  }
+}
+
+fn finish_story(
+  mut events: EventReader<StoryFinished>,
+  mut next_state: ResMut<NextState<NahidaState>>,
+) {
+  if events.iter().count() > 0 {
+    next_state.set(NahidaState::Menu);
+  }
+}
+
+/// `F6` picks a slot to save to
+fn open_save_screen(
+  mut command: Commands,
+  keys: Res<Input<KeyCode>>,
+  overlays: Query<(), Overlays>,
+  storage: Option<Res<SaveStorage>>,
+  fonts: Res<NahidaFonts>,
+) {
+  if !keys.just_pressed(KeyCode::F6) || !overlays.is_empty() {
+    return;
+  }
+
+  let style = button_style(&fonts);
+
+  command
+    .spawn((
+      PlayingComponent,
+      SaveScreen,
+      NodeBundle {
+        style: Style {
+          position_type: PositionType::Absolute,
+          size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+          flex_direction: FlexDirection::Column,
+          align_items: AlignItems::Center,
+          justify_content: JustifyContent::Center,
+          ..Default::default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+        focus_policy: FocusPolicy::Block,
+        z_index: ZIndex::Global(10),
+        ..Default::default()
+      },
+    ))
+    .with_children(|screen| {
+      for n in 1..=SAVE_SLOTS {
+        let timestamp = storage
+          .as_ref()
+          .and_then(|x| x.load(SaveSlot::Numbered(n)).ok())
+          .map(|data| format_timestamp(data.timestamp))
+          .unwrap_or_else(|| "空".to_string());
+
+        screen
+          .spawn((
+            SaveButton(n),
+            ButtonBundle {
+              style: Style {
+                size: Size::width(Val::Px(480.0)),
+                margin: UiRect::vertical(Val::Px(8.0)),
+                padding: UiRect::all(Val::Px(12.0)),
+                justify_content: JustifyContent::SpaceBetween,
+                ..Default::default()
+              },
+              background_color: BUTTON_COLOR.into(),
+              ..Default::default()
+            },
+          ))
+          .with_children(|button| {
+            button.spawn(TextBundle::from_section(format!("存档 {n}"), style.clone()));
+            button.spawn(TextBundle::from_section(timestamp, style.clone()));
+          });
+      }
+    });
+}
+
+fn click_save_button(
+  mut command: Commands,
+  buttons: Query<(&Interaction, &SaveButton), Changed<Interaction>>,
+  screens: Query<Entity, With<SaveScreen>>,
+  mut save: EventWriter<SaveRequest>,
+) {
+  for (interaction, SaveButton(n)) in buttons.iter() {
+    if *interaction != Interaction::Clicked {
+      continue;
+    }
+
+    save.send(SaveRequest(SaveSlot::Numbered(*n)));
+    for entity in screens.iter() {
+      command.entity(entity).despawn_recursive();
+    }
+  }
+}
+
+/// `Esc` or right click goes back to the story
+fn close_save_screen(
+  mut command: Commands,
+  keys: Res<Input<KeyCode>>,
+  buttons: Res<Input<MouseButton>>,
+  screens: Query<Entity, With<SaveScreen>>,
+) {
+  if !(keys.just_pressed(KeyCode::Escape) || buttons.just_pressed(MouseButton::Right)) {
+    return;
+  }
+
+  for entity in screens.iter() {
+    command.entity(entity).despawn_recursive();
+  }
+}
+
+#[allow(clippy::type_complexity)]
+fn highlight_save_buttons(
+  mut buttons: Query<
+    (&Interaction, &mut BackgroundColor),
+    (With<SaveButton>, Changed<Interaction>),
+  >,
+) {
+  for (interaction, mut color) in buttons.iter_mut() {
+    *color = match interaction {
+      Interaction::None => BUTTON_COLOR.into(),
+      _ => HOVERED_COLOR.into(),
+    };
+  }
+}
+
+#[allow(clippy::type_complexity)]
+fn destroy_playing(
+  mut command: Commands,
+  query: Query<Entity, Or<(With<PlayingComponent>, With<StageComponent>, With<Outgoing>)>>,
+  audio: Res<Audio>,
+  sinks: Res<Assets<AudioSink>>,
+  mut bgm: ResMut<NahidaBgm>,
+  mut voice: ResMut<NahidaVoice>,
+  mut mode: ResMut<PlayMode>,
+) {
+  for entity in query.iter() {
+    command.entity(entity).despawn_recursive();
+  }
+
+  bgm.play(None, &audio, &sinks);
+  if let Some(sink) = voice.0.take().and_then(|x| sinks.get(&x)) {
+    sink.stop();
+  }
+  *mode = PlayMode::Normal;
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, path::PathBuf};
+
+  use bevy::{input::mouse::MouseWheel, prelude::*};
+  use nahida_core::player::PlayerConfig;
+  use nahida_parser::parse_story;
+
+  use crate::{
+    asset::story::{StoryAsset, StoryAssetPlugin},
+    backlog::NahidaBacklogPlugin,
+    mode::NahidaModePlugin,
+    player::{NahidaPlayer, NahidaPlayerConfig, NahidaPlayerPlugin},
+    save::{NahidaSavePlugin, SaveRequest, SaveSlot, SaveStorage},
+    settings::NahidaSettings,
+    stage::{NahidaBgm, RestoreStage},
+    state::{
+      loading::{NahidaFonts, NahidaResources},
+      NahidaState,
+    },
+    NahidaEntryPoint,
+  };
+
+  use super::{ChoiceButton, DialogueName, DialogueText, PlayingPlugin, SaveButton};
+
+  fn setup(config: PlayerConfig) -> App {
+    let entry = PathBuf::from("/story.md");
+    let script = "# 纳西妲\n\n「一」\n\n「二」\n\n- 好啊\n- 还是算了\n";
+
+    let mut app = App::new();
+    app
+      .add_plugins(MinimalPlugins)
+      .add_plugin(AssetPlugin::default())
+      .add_plugin(StoryAssetPlugin)
+      .add_asset::<AudioSource>()
+      .add_asset::<AudioSink>()
+      .init_resource::<Audio>()
+      .init_resource::<ClearColor>()
+      .init_resource::<Input<KeyCode>>()
+      .init_resource::<Input<MouseButton>>()
+      .init_resource::<NahidaFonts>()
+      .init_resource::<NahidaResources>()
+      .init_resource::<NahidaBgm>()
+      .init_resource::<NahidaSettings>()
+      .add_event::<MouseWheel>()
+      .add_event::<RestoreStage>()
+      .add_event::<SaveRequest>()
+      .add_state::<NahidaState>()
+      .insert_resource(NextState(Some(NahidaState::Playing)))
+      .insert_resource(NahidaEntryPoint(entry.display().to_string()))
+      .insert_resource(NahidaPlayerConfig(config))
+      .add_plugin(NahidaPlayerPlugin)
+      .add_plugin(NahidaBacklogPlugin)
+      .add_plugin(NahidaModePlugin)
+      .add_plugin(PlayingPlugin);
+
+    let handle = app
+      .world
+      .resource_mut::<Assets<StoryAsset>>()
+      .add(StoryAsset {
+        story: parse_story(script).unwrap(),
+      });
+    let mut resources = app.world.resource_mut::<NahidaResources>();
+    resources.insert_story(entry, handle);
+    app
+  }
+
+  fn press(app: &mut App, key: KeyCode) {
+    let mut input = app.world.resource_mut::<Input<KeyCode>>();
+    input.release_all();
+    input.clear();
+    input.press(key);
+    app.update();
+  }
+
+  fn text<T: Component>(app: &mut App) -> String {
+    let mut query = app.world.query_filtered::<&Text, With<T>>();
+    let text = query.single(&app.world);
+    text.sections.iter().map(|x| x.value.as_str()).collect()
+  }
+
+  fn options(app: &mut App) -> Vec<usize> {
+    let mut query = app.world.query::<&ChoiceButton>();
+    query.iter(&app.world).map(|x| x.0).collect()
+  }
+
+  fn save_buttons(app: &mut App) -> Vec<u32> {
+    let mut query = app.world.query::<&SaveButton>();
+    query.iter(&app.world).map(|x| x.0).collect()
+  }
+
+  #[test]
+  fn test_advance_on_input() {
+    let mut app = setup(PlayerConfig {
+      text_speed: f32::INFINITY,
+      ..Default::default()
+    });
+    app.update();
+    app.update();
+    assert_eq!(text::<DialogueName>(&mut app), "纳西妲");
+    assert_eq!(text::<DialogueText>(&mut app), "「一」");
+
+    press(&mut app, KeyCode::Space);
+    assert_eq!(text::<DialogueText>(&mut app), "「二」");
+
+    // the choice waits for an option to be picked
+    press(&mut app, KeyCode::Return);
+    app.update();
+    assert_eq!(options(&mut app), vec![0, 1]);
+    press(&mut app, KeyCode::Space);
+    assert_eq!(options(&mut app), vec![0, 1]);
+
+    let mut query = app.world.query::<(&ChoiceButton, &mut Interaction)>();
+    for (button, mut interaction) in query.iter_mut(&mut app.world) {
+      if button.0 == 1 {
+        *interaction = Interaction::Clicked;
+      }
+    }
+    app.update();
+    app.update();
+    assert_eq!(
+      app.world.resource::<State<NahidaState>>().0,
+      NahidaState::Menu
+    );
+  }
+
+  #[test]
+  fn test_complete_typing() {
+    let mut app = setup(PlayerConfig {
+      text_speed: 0.1,
+      ..Default::default()
+    });
+    app.update();
+    app.update();
+    assert_eq!(text::<DialogueText>(&mut app), "");
+
+    // the first press shows the whole text, the next one goes on
+    press(&mut app, KeyCode::Space);
+    assert_eq!(text::<DialogueText>(&mut app), "「一」");
+    press(&mut app, KeyCode::Space);
+    assert_eq!(text::<DialogueText>(&mut app), "");
+
+    let player = &app.world.resource::<NahidaPlayer>().player;
+    assert_eq!(player.cursor().map(|x| x.frames[0].step), Some(1));
+  }
+
+  #[test]
+  fn test_save_screen() {
+    let dir = std::env::temp_dir().join(format!("nahida-save-screen-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let storage = SaveStorage { dir };
+
+    let mut app = setup(PlayerConfig {
+      text_speed: f32::INFINITY,
+      ..Default::default()
+    });
+    app
+      .insert_resource(storage.clone())
+      .add_plugin(NahidaSavePlugin);
+    app.update();
+    app.update();
+
+    press(&mut app, KeyCode::F6);
+    assert_eq!(save_buttons(&mut app), vec![1, 2, 3, 4, 5, 6]);
+
+    // the story waits while the screen is open
+    press(&mut app, KeyCode::Space);
+    assert_eq!(text::<DialogueText>(&mut app), "「一」");
+
+    let mut input = app.world.resource_mut::<Input<KeyCode>>();
+    input.release_all();
+    input.clear();
+    let mut query = app.world.query::<(&SaveButton, &mut Interaction)>();
+    for (button, mut interaction) in query.iter_mut(&mut app.world) {
+      if button.0 == 2 {
+        *interaction = Interaction::Clicked;
+      }
+    }
+    app.update();
+    app.update();
+    assert_eq!(storage.slots(), vec![SaveSlot::Numbered(2)]);
+    assert!(save_buttons(&mut app).is_empty());
+    assert_eq!(text::<DialogueText>(&mut app), "「一」");
+
+    // closing the screen saves nothing
+    press(&mut app, KeyCode::F6);
+    press(&mut app, KeyCode::Escape);
+    assert!(save_buttons(&mut app).is_empty());
+    assert_eq!(storage.slots(), vec![SaveSlot::Numbered(2)]);
+
+    fs::remove_dir_all(&storage.dir).unwrap();
+  }
+}
@@ -0,0 +1,201 @@
+use std::{path::PathBuf, time::Duration};
+
+use bevy::prelude::*;
+use nahida_core::text::{RichText, TextSpan, TextStyle as SpanStyle};
+
+/// color of `` `code` `` spans
+pub const CODE_COLOR: Color = Color::rgb(0.95, 0.65, 0.3);
+/// color of `**strong**` spans
+pub const STRONG_COLOR: Color = Color::rgb(0.55, 0.85, 0.45);
+/// color of `*emphasis*` spans
+pub const EMPHASIS_COLOR: Color = Color::rgb(0.6, 0.8, 1.0);
+
+/// color of the span, the one set in the text goes first
+fn span_color(span: &SpanStyle, base: Color) -> Color {
+  match span {
+    SpanStyle {
+      color: Some(color), ..
+    } => Color::rgba(color.r, color.g, color.b, color.a),
+    SpanStyle { code: true, .. } => CODE_COLOR,
+    SpanStyle { bold: true, .. } => STRONG_COLOR,
+    SpanStyle { italic: true, .. } => EMPHASIS_COLOR,
+    _ => base,
+  }
+}
+
+/// sections of the rich text, in the given font
+pub fn rich_text_sections(text: &RichText, style: &TextStyle) -> Vec<TextSection> {
+  text
+    .spans
+    .iter()
+    .filter_map(|span| match span {
+      TextSpan::Text { text, style: span } => Some(TextSection::new(
+        text.clone(),
+        TextStyle {
+          color: span_color(span, style.color),
+          ..style.clone()
+        },
+      )),
+      TextSpan::Break => Some(TextSection::new("\n", style.clone())),
+      TextSpan::Pause(_) | TextSpan::Speed(_) | TextSpan::Sfx(_) => None,
+    })
+    .collect()
+}
+
+/// text typed character by character
+#[derive(Component, Default)]
+pub struct Typewriter {
+  text: RichText,
+  /// characters per second
+  speed: f32,
+  elapsed: Duration,
+  typed: RichText,
+  /// sfx in the typed text already played
+  cues: usize,
+}
+
+impl Typewriter {
+  pub fn new(text: RichText, speed: f32) -> Self {
+    Self {
+      text,
+      speed,
+      ..Default::default()
+    }
+  }
+
+  pub fn typed(&self) -> &RichText {
+    &self.typed
+  }
+
+  pub fn speed(&self) -> f32 {
+    self.speed
+  }
+
+  /// type at another speed, keeping about as much of the text typed
+  pub fn set_speed(&mut self, speed: f32) {
+    let before = self.text.typing_time(self.speed);
+    if !before.is_zero() {
+      let ratio = self.elapsed.as_secs_f64() / before.as_secs_f64();
+      self.elapsed = self.text.typing_time(speed).mul_f64(ratio);
+    }
+    self.speed = speed;
+  }
+
+  pub fn finished(&self) -> bool {
+    self.elapsed >= self.text.typing_time(self.speed)
+  }
+
+  /// show the whole text at once, the sfx skipped are not played
+  pub fn complete(&mut self) {
+    self.elapsed = self.text.typing_time(self.speed);
+    self.cues = self.text.sfx().count();
+  }
+
+  /// type for a while, returning whether more of the text is typed
+  pub fn tick(&mut self, delta: Duration) -> bool {
+    self.elapsed += delta;
+    let typed = self.text.typed(self.elapsed, self.speed);
+    if typed == self.typed {
+      return false;
+    }
+
+    self.typed = typed;
+    true
+  }
+
+  /// sfx typed since the last call
+  pub fn take_cues(&mut self) -> Vec<PathBuf> {
+    let cues = self
+      .typed
+      .sfx()
+      .skip(self.cues)
+      .cloned()
+      .collect::<Vec<_>>();
+    self.cues += cues.len();
+    cues
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::prelude::*;
+  use nahida_core::text::{RichText, TextSpan, TextStyle as SpanStyle};
+
+  use std::{path::PathBuf, time::Duration};
+
+  use super::{rich_text_sections, Typewriter, CODE_COLOR, STRONG_COLOR};
+
+  #[test]
+  fn test_rich_text_sections() {
+    let text = RichText::new(vec![
+      TextSpan::plain("a"),
+      TextSpan::Break,
+      TextSpan::Text {
+        text: "b".into(),
+        style: SpanStyle {
+          code: true,
+          ..Default::default()
+        },
+      },
+      TextSpan::Text {
+        text: "c".into(),
+        style: SpanStyle {
+          bold: true,
+          ..Default::default()
+        },
+      },
+      TextSpan::Text {
+        text: "d".into(),
+        style: SpanStyle {
+          italic: true,
+          color: Some(nahida_core::Color::rgba(1.0, 0.0, 0.0, 1.0)),
+          ..Default::default()
+        },
+      },
+    ]);
+    let style = TextStyle {
+      color: Color::WHITE,
+      ..Default::default()
+    };
+
+    let sections = rich_text_sections(&text, &style)
+      .into_iter()
+      .map(|x| (x.value, x.style.color))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      sections,
+      vec![
+        ("a".to_string(), Color::WHITE),
+        ("\n".to_string(), Color::WHITE),
+        ("b".to_string(), CODE_COLOR),
+        ("c".to_string(), STRONG_COLOR),
+        ("d".to_string(), Color::rgba(1.0, 0.0, 0.0, 1.0)),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_typewriter() {
+    let text = RichText::new(vec![
+      TextSpan::plain("一"),
+      TextSpan::Sfx("./a.mp3".into()),
+      TextSpan::plain("二"),
+      TextSpan::Sfx("./b.mp3".into()),
+      TextSpan::plain("三"),
+    ]);
+    let mut typewriter = Typewriter::new(text.clone(), 1.0);
+
+    assert!(!typewriter.tick(Duration::from_millis(500)));
+    assert!(typewriter.tick(Duration::from_millis(500)));
+    assert_eq!(typewriter.typed().to_string(), "一");
+    assert_eq!(typewriter.take_cues(), vec![PathBuf::from("./a.mp3")]);
+    assert!(typewriter.take_cues().is_empty());
+
+    // the cues skipped are not played
+    typewriter.complete();
+    assert!(typewriter.finished());
+    assert!(typewriter.tick(Duration::ZERO));
+    assert_eq!(typewriter.typed(), &text);
+    assert!(typewriter.take_cues().is_empty());
+  }
+}
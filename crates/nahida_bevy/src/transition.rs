@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use nahida_core::story::{Transition, TransitionType};
+
+use crate::{
+  animation::{shake_offset, AnimationSet},
+  material::stage::{transition_kind, StageMaterial},
+  WindowSize,
+};
+
+pub struct NahidaTransitionPlugin;
+
+impl Plugin for NahidaTransitionPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_system(play_transition.after(AnimationSet));
+  }
+}
+
+/// transition playing on a background or a figure
+#[derive(Component, Clone)]
+pub struct ImageTransition {
+  transition: Transition,
+  /// loaded mask of the rule transition
+  mask: Option<Handle<Image>>,
+  elapsed: Duration,
+}
+
+impl ImageTransition {
+  pub fn new(transition: Transition) -> Self {
+    Self {
+      transition,
+      mask: None,
+      elapsed: Duration::ZERO,
+    }
+  }
+
+  pub fn with_mask(mut self, mask: Handle<Image>) -> Self {
+    self.mask = Some(mask);
+    self
+  }
+
+  pub fn finished(&self) -> bool {
+    self.elapsed >= self.transition.time
+  }
+
+  /// jump to the end of the transition
+  pub fn skip(&mut self) {
+    self.elapsed = self.transition.time;
+  }
+
+  fn progress(&self) -> f32 {
+    let x = match self.transition.time {
+      Duration::ZERO => 1.0,
+      time => self.elapsed.as_secs_f32() / time.as_secs_f32(),
+    };
+    self.transition.easing.easing(x.min(1.0))
+  }
+}
+
+fn play_transition(
+  mut query: Query<(
+    &mut ImageTransition,
+    &mut Transform,
+    Option<&Handle<StageMaterial>>,
+  )>,
+  mut materials: ResMut<Assets<StageMaterial>>,
+  window_size: Res<WindowSize>,
+  time: Res<Time>,
+) {
+  for (mut transition, mut transform, material) in query.iter_mut() {
+    transition.elapsed = (transition.elapsed + time.delta()).min(transition.transition.time);
+    let progress = transition.progress();
+
+    if transition.transition.shake && !transition.finished() {
+      transform.translation.x += shake_offset(progress, window_size.0);
+    }
+
+    if let Some(handle) = material {
+      let kind = transition_kind(&transition.transition.ty);
+      let softness = match transition.transition.ty {
+        TransitionType::Rule { softness, .. } => softness,
+        _ => 0.0,
+      };
+
+      // avoid rebuilding the bind group when nothing changes
+      let changed = |x: &StageMaterial| {
+        (x.transition, x.progress, x.softness) != (kind, progress, softness)
+          || x.mask != transition.mask
+      };
+      if materials.get(handle).is_some_and(changed) {
+        if let Some(material) = materials.get_mut(handle) {
+          material.transition = kind;
+          material.progress = progress;
+          material.softness = softness;
+          material.mask = transition.mask.clone();
+        }
+      }
+    }
+  }
+}
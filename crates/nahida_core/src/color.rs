@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+/// color in sRGB, each channel from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Color {
+  pub r: f32,
+  pub g: f32,
+  pub b: f32,
+  pub a: f32,
+}
+
+impl Color {
+  pub const WHITE: Color = Color::rgba(1.0, 1.0, 1.0, 1.0);
+
+  pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+    Self { r, g, b, a }
+  }
+
+  /// parse `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`
+  pub fn hex(hex: &str) -> Option<Self> {
+    let hex = hex.strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+      return None;
+    }
+
+    let channel = |index: usize, width: usize| {
+      let value = u8::from_str_radix(&hex[index * width..(index + 1) * width], 16).ok()?;
+      // `#f80` is the same as `#ff8800`
+      let value = if width == 1 { value * 17 } else { value };
+      Some(value as f32 / 255.0)
+    };
+
+    let (width, alpha) = match hex.len() {
+      3 => (1, false),
+      4 => (1, true),
+      6 => (2, false),
+      8 => (2, true),
+      _ => return None,
+    };
+    let a = if alpha { channel(3, width)? } else { 1.0 };
+
+    Some(Self::rgba(
+      channel(0, width)?,
+      channel(1, width)?,
+      channel(2, width)?,
+      a,
+    ))
+  }
+
+  pub fn lerp(&self, other: &Color, t: f32) -> Self {
+    Self::rgba(
+      self.r + (other.r - self.r) * t,
+      self.g + (other.g - self.g) * t,
+      self.b + (other.b - self.b) * t,
+      self.a + (other.a - self.a) * t,
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Color;
+
+  #[test]
+  fn test_hex() {
+    assert_eq!(Color::hex("#fff"), Some(Color::WHITE));
+    assert_eq!(
+      Color::hex("#ff000080"),
+      Some(Color::rgba(1.0, 0.0, 0.0, 128.0 / 255.0))
+    );
+    assert_eq!(Color::hex("#f80"), Color::hex("#ff8800"));
+    assert_eq!(Color::hex("ff8800"), None);
+    assert_eq!(
+      Color::hex("#ff88"),
+      Some(Color::rgba(1.0, 1.0, 136.0 / 255.0, 136.0 / 255.0))
+    );
+    assert_eq!(Color::hex("#ggg"), None);
+    assert_eq!(Color::hex("#12345"), None);
+  }
+}
@@ -1,13 +1,51 @@
-use crate::math;
+use std::f32::consts::TAU;
 
-#[derive(Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EasingFunction {
   Linear,
 
   StepStart,
   StepEnd,
+  Steps {
+    count: u32,
+    position: StepPosition,
+  },
 
   CubicBezier(CubicBezier),
+
+  /// piecewise linear function through `(input, output)` points sorted by input
+  LinearStops(Vec<(f32, f32)>),
+
+  /// damped spring pulled from 0 to 1, stretched to settle within the duration
+  Spring {
+    mass: f32,
+    stiffness: f32,
+    damping: f32,
+  },
+  /// falls onto 1 and bounces `bounces` times, each half as fast as the previous one
+  Bounce {
+    bounces: u32,
+  },
+  /// oscillates around 1 with a decaying amplitude
+  Elastic {
+    amplitude: f32,
+    period: f32,
+  },
+  /// overshoots 1 before settling back
+  Back {
+    overshoot: f32,
+  },
+}
+
+/// `<step-position>` of `steps()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepPosition {
+  JumpStart,
+  JumpEnd,
+  JumpNone,
+  JumpBoth,
 }
 
 impl EasingFunction {
@@ -42,6 +80,105 @@ impl EasingFunction {
   pub fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32) -> Self {
     Self::CubicBezier(CubicBezier::new(x1, y1, x2, y2))
   }
+
+  pub fn steps(count: u32, position: StepPosition) -> Self {
+    Self::Steps { count, position }
+  }
+
+  /// points should be sorted by input
+  pub fn linear_stops(points: Vec<(f32, f32)>) -> Self {
+    Self::LinearStops(points)
+  }
+
+  pub fn spring(mass: f32, stiffness: f32, damping: f32) -> Self {
+    Self::Spring {
+      mass,
+      stiffness,
+      damping,
+    }
+  }
+
+  pub fn bounce(bounces: u32) -> Self {
+    Self::Bounce { bounces }
+  }
+
+  pub fn elastic(amplitude: f32, period: f32) -> Self {
+    Self::Elastic { amplitude, period }
+  }
+
+  pub fn back(overshoot: f32) -> Self {
+    Self::Back { overshoot }
+  }
+}
+
+/// the spring is considered at rest once its oscillation decays below this
+const SPRING_REST: f32 = 1e-3;
+
+/// speed lost on every bounce
+const BOUNCE_RESTITUTION: f32 = 0.5;
+
+fn spring(mass: f32, stiffness: f32, damping: f32, x: f32) -> f32 {
+  let omega = (stiffness / mass).sqrt();
+  let zeta = damping / (2.0 * (stiffness * mass).sqrt());
+
+  // the slowest decay rate decides how long it takes to rest
+  let decay = match zeta {
+    _ if zeta < 1.0 => zeta * omega,
+    _ => omega * (zeta - (zeta * zeta - 1.0).sqrt()),
+  };
+  let t = x * -SPRING_REST.ln() / decay;
+
+  match zeta {
+    _ if zeta < 1.0 => {
+      let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+      let envelope = (-zeta * omega * t).exp();
+      1.0 - envelope * ((omega_d * t).cos() + zeta * omega / omega_d * (omega_d * t).sin())
+    }
+    _ if zeta == 1.0 => 1.0 - (-omega * t).exp() * (1.0 + omega * t),
+    _ => {
+      let root = (zeta * zeta - 1.0).sqrt();
+      let r1 = -omega * (zeta - root);
+      let r2 = -omega * (zeta + root);
+      1.0 - (r2 * (r1 * t).exp() - r1 * (r2 * t).exp()) / (r2 - r1)
+    }
+  }
+}
+
+fn bounce(bounces: u32, x: f32) -> f32 {
+  // the fall takes 1 unit of time, every bounce takes `2 * r^i`
+  let total = 1.0
+    + (1..=bounces)
+      .map(|i| 2.0 * BOUNCE_RESTITUTION.powi(i as i32))
+      .sum::<f32>();
+  let t = x * total;
+
+  if t < 1.0 {
+    return t * t;
+  }
+
+  let mut start = 1.0;
+  for i in 1..=bounces {
+    let speed = BOUNCE_RESTITUTION.powi(i as i32);
+    let width = 2.0 * speed;
+    if t < start + width {
+      let t = t - start - speed;
+      return 1.0 - speed * speed + t * t;
+    }
+    start += width;
+  }
+
+  1.0
+}
+
+fn elastic(amplitude: f32, period: f32, x: f32) -> f32 {
+  let amplitude = amplitude.max(1.0);
+  let shift = period / TAU * (1.0 / amplitude).asin();
+  amplitude * 2f32.powf(-10.0 * x) * ((x - shift) * TAU / period).sin() + 1.0
+}
+
+fn back(overshoot: f32, x: f32) -> f32 {
+  let x = x - 1.0;
+  1.0 + (overshoot + 1.0) * x * x * x + overshoot * x * x
 }
 
 impl EasingFunction {
@@ -62,16 +199,72 @@ impl EasingFunction {
           1.0
         }
       }
+      EasingFunction::Steps { count, position } => {
+        let count = *count as f32;
+        let jumps = match position {
+          StepPosition::JumpStart | StepPosition::JumpEnd => count,
+          StepPosition::JumpNone => count - 1.0,
+          StepPosition::JumpBoth => count + 1.0,
+        };
+        // `steps(0)` and `steps(1, jump-none)` are invalid in css, jump once for them
+        let jumps = jumps.max(1.0);
+
+        let mut step = (x.clamp(0.0, 1.0) * count).floor();
+        if matches!(position, StepPosition::JumpStart | StepPosition::JumpBoth) {
+          step += 1.0;
+        }
+
+        step.clamp(0.0, jumps) / jumps
+      }
+      EasingFunction::LinearStops(points) => {
+        let x = x.clamp(0.0, 1.0);
+
+        match points.iter().rposition(|(input, _)| *input <= x) {
+          None => points.first().map_or(x, |(_, output)| *output),
+          Some(index) if index + 1 == points.len() => points[index].1,
+          Some(index) => {
+            let (x1, y1) = points[index];
+            let (x2, y2) = points[index + 1];
+            y1 + (y2 - y1) * (x - x1) / (x2 - x1)
+          }
+        }
+      }
+      // the easings below may overshoot, only the input gets clamped
       EasingFunction::CubicBezier(cubic) => match () {
-        _ if x < 0.0 => 0.0,
-        _ if x > 1.0 => 1.0,
-        _ => cubic.sample_y(cubic.solve_x(x)).clamp(0.0, 1.0),
+        _ if x <= 0.0 => 0.0,
+        _ if x >= 1.0 => 1.0,
+        _ => cubic.sample_y(cubic.solve_x(x)),
       },
+      EasingFunction::Spring {
+        mass,
+        stiffness,
+        damping,
+      } => match () {
+        _ if x <= 0.0 => 0.0,
+        _ if x >= 1.0 => 1.0,
+        _ => spring(*mass, *stiffness, *damping, x),
+      },
+      EasingFunction::Bounce { bounces } => bounce(*bounces, x.clamp(0.0, 1.0)),
+      EasingFunction::Elastic { amplitude, period } => match () {
+        _ if x <= 0.0 => 0.0,
+        _ if x >= 1.0 => 1.0,
+        _ => elastic(*amplitude, *period, x),
+      },
+      EasingFunction::Back { overshoot } => back(*overshoot, x.clamp(0.0, 1.0)),
     }
   }
 }
 
-#[derive(Debug, PartialEq)]
+/// size of the table of precomputed `x(t)`
+const SAMPLES: usize = 11;
+const SAMPLE_STEP: f32 = 1.0 / (SAMPLES - 1) as f32;
+
+const NEWTON_ITERATIONS: usize = 4;
+const NEWTON_MIN_SLOPE: f32 = 1e-3;
+const BISECTION_MAX_ITERATIONS: usize = 32;
+const PRECISION: f32 = 1e-6;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CubicBezier {
   ax: f32,
   bx: f32,
@@ -80,6 +273,9 @@ pub struct CubicBezier {
   ay: f32,
   by: f32,
   cy: f32,
+
+  /// `x(t)` for `t` in `0, 0.1, ..., 1`
+  samples: [f32; SAMPLES],
 }
 
 impl CubicBezier {
@@ -92,28 +288,274 @@ impl CubicBezier {
     let by = 3.0 * (y2 - y1) - cy; /* -6 * y1 + 3 * y2 */
     let ay = 1.0 - cy - by; /* 1 + 3 * y1 - 3 * y2 */
 
-    Self {
+    let mut cubic = Self {
       ax,
       bx,
       cx,
       ay,
       by,
       cy,
+      samples: [0.0; SAMPLES],
+    };
+    for (i, sample) in cubic.samples.iter_mut().enumerate() {
+      let t = i as f32 * SAMPLE_STEP;
+      *sample = ((ax * t + bx) * t + cx) * t;
     }
+
+    cubic
   }
 
-  // /// get x (t)
-  // fn sample_x(&self, t: f32) -> f32 {
-  //   ((self.ax * t + self.bx) * t + self.cx) * t
-  // }
+  /// get x (t)
+  fn sample_x(&self, t: f32) -> f32 {
+    ((self.ax * t + self.bx) * t + self.cx) * t
+  }
 
   /// get y (t)
   fn sample_y(&self, t: f32) -> f32 {
     ((self.ay * t + self.by) * t + self.cy) * t
   }
 
+  /// get x'(t)
+  fn sample_dx(&self, t: f32) -> f32 {
+    (3.0 * self.ax * t + 2.0 * self.bx) * t + self.cx
+  }
+
   /// get t from x
+  ///
+  /// starts from the precomputed samples, refines with Newton-Raphson
+  /// and falls back to bisection where the curve is too flat
   fn solve_x(&self, x: f32) -> f32 {
-    math::cubic_solve4(self.ax, self.bx, self.cx, -x)
+    // find the sample interval containing x
+    let index = self.samples[1..SAMPLES - 1]
+      .iter()
+      .take_while(|sample| **sample <= x)
+      .count();
+    let (lower, upper) = (self.samples[index], self.samples[index + 1]);
+    let start = index as f32 * SAMPLE_STEP;
+
+    // guess t by interpolating between the samples
+    let guess = match upper - lower {
+      width if width > 0.0 => start + (x - lower) / width * SAMPLE_STEP,
+      _ => start,
+    };
+
+    if self.sample_dx(guess) >= NEWTON_MIN_SLOPE {
+      if let Some(t) = self.newton_raphson(x, guess) {
+        return t;
+      }
+    }
+
+    self.bisection(x, start, start + SAMPLE_STEP)
+  }
+
+  fn newton_raphson(&self, x: f32, mut t: f32) -> Option<f32> {
+    for _ in 0..NEWTON_ITERATIONS {
+      let error = self.sample_x(t) - x;
+      if error.abs() < PRECISION {
+        break;
+      }
+      let slope = self.sample_dx(t);
+      if slope.abs() < NEWTON_MIN_SLOPE {
+        return None;
+      }
+      t -= error / slope;
+    }
+
+    let converged = (0.0..=1.0).contains(&t) && (self.sample_x(t) - x).abs() < PRECISION * 10.0;
+    converged.then_some(t)
+  }
+
+  fn bisection(&self, x: f32, mut lower: f32, mut upper: f32) -> f32 {
+    let mut t = (lower + upper) / 2.0;
+
+    for _ in 0..BISECTION_MAX_ITERATIONS {
+      let error = self.sample_x(t) - x;
+      if error.abs() < PRECISION {
+        break;
+      }
+      if error > 0.0 {
+        upper = t;
+      } else {
+        lower = t;
+      }
+      t = (lower + upper) / 2.0;
+    }
+
+    t
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{EasingFunction, StepPosition};
+
+  /// compare `easing(0), easing(0.1), ..., easing(1)` with the expected values
+  fn assert_samples(easing: EasingFunction, expected: [f32; 11]) {
+    for (i, expected) in expected.into_iter().enumerate() {
+      let x = i as f32 / 10.0;
+      let y = easing.easing(x);
+      assert!(
+        (y - expected).abs() < 1e-4,
+        "{easing:?} at {x}: got {y}, expected {expected}"
+      );
+    }
+  }
+
+  /// values computed by browsers for the css keywords
+  #[test]
+  fn test_named_easing() {
+    assert_samples(
+      EasingFunction::linear(),
+      [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0],
+    );
+    assert_samples(
+      EasingFunction::ease(),
+      [
+        0.0, 0.094796, 0.295244, 0.513315, 0.682541, 0.802403, 0.885229, 0.940765, 0.975625,
+        0.994316, 1.0,
+      ],
+    );
+    assert_samples(
+      EasingFunction::ease_in(),
+      [
+        0.0, 0.017027, 0.062282, 0.129577, 0.214861, 0.315357, 0.42912, 0.554814, 0.691634,
+        0.839428, 1.0,
+      ],
+    );
+    assert_samples(
+      EasingFunction::ease_out(),
+      [
+        0.0, 0.160572, 0.308366, 0.445186, 0.57088, 0.684643, 0.785139, 0.870423, 0.937718,
+        0.982973, 1.0,
+      ],
+    );
+    assert_samples(
+      EasingFunction::ease_in_out(),
+      [
+        0.0, 0.019722, 0.08166, 0.187396, 0.331884, 0.5, 0.668116, 0.812604, 0.91834, 0.980278, 1.0,
+      ],
+    );
+    assert_samples(
+      EasingFunction::step_start(),
+      [0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0],
+    );
+    assert_samples(
+      EasingFunction::step_end(),
+      [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+    );
+  }
+
+  /// curves where a closed-form solution divides by zero or has three real roots
+  #[test]
+  fn test_cubic_bezier_stability() {
+    let curves = [
+      (1.0 / 3.0, 0.0, 2.0 / 3.0, 1.0),
+      (0.0, 0.0, 1.0, 1.0),
+      (1.0, 0.0, 0.0, 1.0),
+      (0.5, 0.0, 0.5, 1.0),
+      (0.0, 1.0, 0.0, 1.0),
+      (1.0, 0.0, 1.0, 0.0),
+    ];
+
+    for (x1, y1, x2, y2) in curves {
+      let easing = EasingFunction::cubic_bezier(x1, y1, x2, y2);
+      let mut last = 0.0;
+
+      for i in 0..=1000 {
+        let y = easing.easing(i as f32 / 1000.0);
+        assert!(y.is_finite(), "{easing:?} at {i}");
+        assert!(y >= last - 1e-4, "{easing:?} is not monotonic at {i}");
+        last = y;
+      }
+    }
+  }
+
+  #[test]
+  fn test_overshoot() {
+    // values from the easings.net reference implementations
+    assert_samples(
+      EasingFunction::bounce(3),
+      [
+        0.0, 0.075625, 0.3025, 0.680625, 0.91, 0.765625, 0.7725, 0.930625, 0.94, 0.988125, 1.0,
+      ],
+    );
+    assert_samples(
+      EasingFunction::elastic(1.0, 0.3),
+      [
+        0.0, 1.25, 1.125, 0.875, 1.03125, 1.015625, 0.984375, 1.003906, 1.001953, 0.998047, 1.0,
+      ],
+    );
+    assert_samples(
+      EasingFunction::back(1.70158),
+      [
+        0.0, 0.408828, 0.705802, 0.907132, 1.029028, 1.087697, 1.099352, 1.0802, 1.046451,
+        1.014314, 1.0,
+      ],
+    );
+
+    // cubic-bezier is not clamped anymore
+    let easing = EasingFunction::cubic_bezier(0.3, 1.5, 0.7, 1.5);
+    assert!(easing.easing(0.5) > 1.0);
+    assert_eq!(easing.easing(2.0), 1.0);
+  }
+
+  #[test]
+  fn test_spring() {
+    let curves = [(1.0, 100.0, 10.0), (1.0, 100.0, 20.0), (1.0, 100.0, 40.0)];
+
+    for (mass, stiffness, damping) in curves {
+      let easing = EasingFunction::spring(mass, stiffness, damping);
+      assert_eq!(easing.easing(0.0), 0.0);
+      assert_eq!(easing.easing(1.0), 1.0);
+      assert!((easing.easing(0.99) - 1.0).abs() < 1e-2, "{easing:?}");
+    }
+
+    // an underdamped spring overshoots, a critically damped one does not
+    let underdamped = EasingFunction::spring(1.0, 100.0, 10.0);
+    assert!((0..100).any(|i| underdamped.easing(i as f32 / 100.0) > 1.0));
+    let critical = EasingFunction::spring(1.0, 100.0, 20.0);
+    assert!((0..100).all(|i| critical.easing(i as f32 / 100.0) <= 1.0));
+  }
+
+  #[test]
+  fn test_steps() {
+    let easing = EasingFunction::steps(4, StepPosition::JumpEnd);
+    assert_eq!(easing.easing(0.0), 0.0);
+    assert_eq!(easing.easing(0.3), 0.25);
+    assert_eq!(easing.easing(1.0), 1.0);
+
+    let easing = EasingFunction::steps(4, StepPosition::JumpStart);
+    assert_eq!(easing.easing(0.0), 0.25);
+    assert_eq!(easing.easing(0.8), 1.0);
+
+    let easing = EasingFunction::steps(3, StepPosition::JumpNone);
+    assert_eq!(easing.easing(0.0), 0.0);
+    assert_eq!(easing.easing(0.5), 0.5);
+    assert_eq!(easing.easing(1.0), 1.0);
+
+    let easing = EasingFunction::steps(3, StepPosition::JumpBoth);
+    assert_eq!(easing.easing(0.0), 0.25);
+    assert_eq!(easing.easing(0.5), 0.5);
+    assert_eq!(easing.easing(1.0), 1.0);
+    for easing in [
+      EasingFunction::steps(0, StepPosition::JumpEnd),
+      EasingFunction::steps(1, StepPosition::JumpNone),
+    ] {
+      assert!((0..=10).all(|i| (0.0..=1.0).contains(&easing.easing(i as f32 / 10.0))));
+    }
+  }
+
+  #[test]
+  fn test_linear_stops() {
+    let easing = EasingFunction::linear_stops(vec![(0.0, 0.0), (0.75, 0.25), (1.0, 1.0)]);
+    assert_eq!(easing.easing(0.0), 0.0);
+    assert_eq!(easing.easing(0.375), 0.125);
+    assert_eq!(easing.easing(0.875), 0.625);
+    assert_eq!(easing.easing(1.0), 1.0);
+
+    // jumps when two stops share the same input
+    let easing = EasingFunction::linear_stops(vec![(0.0, 0.0), (0.5, 0.0), (0.5, 1.0), (1.0, 1.0)]);
+    assert_eq!(easing.easing(0.25), 0.0);
+    assert_eq!(easing.easing(0.5), 1.0);
   }
 }
@@ -1,7 +1,12 @@
+pub mod color;
 pub mod easing;
 pub mod location;
+pub mod path;
+pub mod player;
+pub mod stage;
 pub mod story;
+pub mod text;
 
-mod math;
-
+pub use color::Color;
 pub use location::{Location, Position, Size};
+pub use stage::StageSnapshot;
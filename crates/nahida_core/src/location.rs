@@ -1,7 +1,9 @@
-#[derive(Debug, Clone, PartialEq, Default)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct Position(pub f32, pub f32);
 
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub enum Size {
   Cover,
   #[default]
@@ -11,8 +13,28 @@ pub enum Size {
   Fixed(f32, f32),
 }
 
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Location {
   pub position: Position,
   pub size: Size,
 }
+
+impl Location {
+  /// positions move smoothly, while sizes of different kinds switch halfway
+  pub fn lerp(&self, other: &Location, t: f32) -> Location {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    let position = Position(
+      lerp(self.position.0, other.position.0),
+      lerp(self.position.1, other.position.1),
+    );
+    let size = match (&self.size, &other.size) {
+      (Size::FixedWidth(a), Size::FixedWidth(b)) => Size::FixedWidth(lerp(*a, *b)),
+      (Size::FixedHeight(a), Size::FixedHeight(b)) => Size::FixedHeight(lerp(*a, *b)),
+      (Size::Fixed(a, b), Size::Fixed(c, d)) => Size::Fixed(lerp(*a, *c), lerp(*b, *d)),
+      (from, _) if t < 0.5 => from.clone(),
+      (_, to) => to.clone(),
+    };
+
+    Location { position, size }
+  }
+}
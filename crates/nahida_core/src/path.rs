@@ -0,0 +1,59 @@
+use std::path::{Path, PathBuf};
+
+/// resolve `dst` relative to the file `src`
+pub fn join_absolute_path(src: &Path, dst: &Path) -> Option<PathBuf> {
+  let mut parent = src.parent();
+  let mut dst = dst;
+
+  loop {
+    match () {
+      _ if dst.starts_with("./") => {
+        dst = dst.strip_prefix("./").unwrap();
+      }
+      _ if dst.starts_with("../") => {
+        dst = dst.strip_prefix("../").unwrap();
+        parent = parent.and_then(|p| p.parent());
+      }
+      _ => break,
+    }
+  }
+
+  parent.map(|p| p.join(dst))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use super::join_absolute_path;
+
+  #[test]
+  fn test_join_absolute_path() {
+    let a = PathBuf::from("index.md");
+    let b = PathBuf::from("story/chapter1/scene1.md");
+    let c = PathBuf::from("./bad_end.md");
+    let d = PathBuf::from("../../happy_end.md");
+
+    assert_eq!(
+      join_absolute_path(&a, &b),
+      Some(PathBuf::from("story/chapter1/scene1.md"))
+    );
+    assert_eq!(
+      join_absolute_path(&a, &c),
+      Some(PathBuf::from("bad_end.md"))
+    );
+    assert_eq!(join_absolute_path(&a, &d), None);
+    assert_eq!(
+      join_absolute_path(&b, &a),
+      Some(PathBuf::from("story/chapter1/index.md"))
+    );
+    assert_eq!(
+      join_absolute_path(&b, &c),
+      Some(PathBuf::from("story/chapter1/bad_end.md"))
+    );
+    assert_eq!(
+      join_absolute_path(&b, &d),
+      Some(PathBuf::from("happy_end.md"))
+    );
+  }
+}
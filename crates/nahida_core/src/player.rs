@@ -0,0 +1,825 @@
+use std::{
+  collections::{BTreeMap, HashMap, HashSet, VecDeque},
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+  path::join_absolute_path,
+  stage::Stage,
+  story::{ChoiceOption, StepId, Story, StoryAction, StoryStep},
+  text::RichText,
+};
+
+/// provides the parsed story files to the player
+pub trait StoryLoader {
+  fn load(&self, path: &Path) -> Option<&Story>;
+}
+
+impl StoryLoader for HashMap<PathBuf, Story> {
+  fn load(&self, path: &Path) -> Option<&Story> {
+    self.get(path)
+  }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PlayerError {
+  #[error("story not found: {0:?}")]
+  StoryNotFound(PathBuf),
+  #[error("the cursor points to nowhere in {0:?}")]
+  InvalidCursor(PathBuf),
+  #[error("a choice should be made first")]
+  ChoicePending,
+  #[error("no choice to make")]
+  NoChoice,
+  #[error("invalid option: {0}")]
+  InvalidOption(usize),
+  #[error("the step is no longer in {0:?}")]
+  StepNotFound(PathBuf),
+  #[error("nothing to roll back")]
+  NoHistory,
+  #[error("cannot roll back across a choice")]
+  RollbackAcrossChoice,
+}
+
+/// how the player behaves, set by the project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerConfig {
+  /// how many steps can be rolled back
+  pub rollback_depth: usize,
+  /// whether rolling back to a choice made is allowed
+  pub rollback_choices: bool,
+  /// time to wait in auto mode, besides reading the text
+  pub auto_delay: Duration,
+  /// time to read a character in auto mode
+  pub auto_char_delay: Duration,
+  /// whether skip mode goes through the steps not seen yet
+  pub skip_unread: bool,
+  /// characters typed per second, `f32::INFINITY` shows the text at once
+  pub text_speed: f32,
+}
+
+impl Default for PlayerConfig {
+  fn default() -> Self {
+    Self {
+      rollback_depth: 100,
+      rollback_choices: true,
+      auto_delay: Duration::from_secs(1),
+      auto_char_delay: Duration::from_millis(60),
+      skip_unread: false,
+      text_speed: 30.0,
+    }
+  }
+}
+
+impl PlayerConfig {
+  /// how long auto mode stays on the text, waiting for the voice to end if there is one
+  pub fn auto_delay(&self, text: &RichText, voice: Option<Duration>) -> Duration {
+    let chars = text
+      .to_string()
+      .chars()
+      .filter(|x| !x.is_whitespace())
+      .count();
+    let reading = self.auto_delay + self.auto_char_delay * chars as u32;
+
+    match voice {
+      Some(voice) => reading.max(voice + self.auto_delay),
+      None => reading,
+    }
+  }
+}
+
+/// steps ever shown to the player, kept across playthroughs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeenSteps(HashSet<(PathBuf, SeenKey)>);
+
+/// a step by the anchor or the source, lines move as the script is edited
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum SeenKey {
+  Anchor(String),
+  Hash(u64),
+}
+
+impl SeenSteps {
+  fn key(cursor: &Cursor) -> Option<(PathBuf, SeenKey)> {
+    let id = &cursor.frames.last()?.id;
+    let key = match &id.anchor {
+      Some(anchor) => SeenKey::Anchor(anchor.clone()),
+      None => SeenKey::Hash(id.hash),
+    };
+    Some((cursor.path.clone(), key))
+  }
+
+  /// mark the step at the cursor, returns whether it is new
+  pub fn insert(&mut self, cursor: &Cursor) -> bool {
+    match Self::key(cursor) {
+      Some(key) => self.0.insert(key),
+      None => false,
+    }
+  }
+
+  pub fn contains(&self, cursor: &Cursor) -> bool {
+    Self::key(cursor).is_some_and(|key| self.0.contains(&key))
+  }
+}
+
+/// the state before a step is played, for rolling back
+#[derive(Debug, Clone)]
+struct History {
+  cursor: Cursor,
+  stage: Stage,
+  variables: BTreeMap<String, String>,
+  /// left by making a choice
+  choice: bool,
+}
+
+/// a level of the cursor, the levels below the first one are branches of choices
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frame {
+  pub step: usize,
+  /// `(action, option)` of the choice made in the step
+  pub choice: Option<(usize, usize)>,
+  /// the step, for finding it again after the script changes
+  #[serde(default)]
+  pub id: StepId,
+}
+
+impl Frame {
+  fn new(step: usize) -> Self {
+    Self {
+      step,
+      choice: None,
+      id: StepId::default(),
+    }
+  }
+}
+
+/// where the player is in the stories
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+  pub path: PathBuf,
+  pub frames: Vec<Frame>,
+}
+
+impl Cursor {
+  /// first step of the story file
+  pub fn new(path: PathBuf) -> Self {
+    Self {
+      path,
+      frames: vec![Frame::new(0)],
+    }
+  }
+
+  /// steps of the innermost level
+  fn steps<'a>(&self, story: &'a Story) -> Option<&'a [StoryStep]> {
+    let (_, parents) = self.frames.split_last()?;
+    let mut steps = &story.steps[..];
+
+    for frame in parents {
+      let (action, option) = frame.choice?;
+      match steps.get(frame.step)?.actions.get(action)? {
+        StoryAction::Choice { options } => steps = &options.get(option)?.steps,
+        _ => return None,
+      }
+    }
+
+    Some(steps)
+  }
+
+  pub fn step<'a>(&self, loader: &'a dyn StoryLoader) -> Option<&'a StoryStep> {
+    let story = loader.load(&self.path)?;
+    let frame = self.frames.last()?;
+    self.steps(story)?.get(frame.step)
+  }
+
+  /// move every level to where its step is now, after the script was edited
+  pub fn relocate(&mut self, loader: &dyn StoryLoader) -> Result<(), PlayerError> {
+    let story = loader
+      .load(&self.path)
+      .ok_or_else(|| PlayerError::StoryNotFound(self.path.clone()))?;
+    let mut steps = &story.steps[..];
+    let levels = self.frames.len();
+
+    for (level, frame) in self.frames.iter_mut().enumerate() {
+      frame.step = frame
+        .id
+        .locate(steps)
+        .ok_or_else(|| PlayerError::StepNotFound(self.path.clone()))?;
+
+      if level + 1 < levels {
+        let invalid = || PlayerError::InvalidCursor(self.path.clone());
+        let (action, option) = frame.choice.ok_or_else(invalid)?;
+        match steps[frame.step].actions.get(action) {
+          Some(StoryAction::Choice { options }) => {
+            steps = &options.get(option).ok_or_else(invalid)?.steps
+          }
+          _ => return Err(invalid()),
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// the choice in the step, along with its index
+fn find_choice(step: &StoryStep) -> Option<(usize, &[ChoiceOption])> {
+  step
+    .actions
+    .iter()
+    .enumerate()
+    .find_map(|(index, action)| match action {
+      StoryAction::Choice { options } => Some((index, &options[..])),
+      _ => None,
+    })
+}
+
+fn find_navigation(step: &StoryStep) -> Option<&Path> {
+  step.actions.iter().find_map(|action| match action {
+    StoryAction::Navigate { url, .. } => Some(url.as_path()),
+    _ => None,
+  })
+}
+
+/// plays the stories step by step, without rendering anything
+#[derive(Debug, Clone)]
+pub struct StoryPlayer {
+  entry: PathBuf,
+  config: PlayerConfig,
+  /// `None` before starting and after finishing
+  cursor: Option<Cursor>,
+  stage: Stage,
+  /// values kept along with the progress, saved and rolled back with it
+  variables: BTreeMap<String, String>,
+  history: VecDeque<History>,
+  finished: bool,
+}
+
+impl StoryPlayer {
+  pub fn new(entry: PathBuf) -> Self {
+    Self {
+      entry,
+      config: PlayerConfig::default(),
+      cursor: None,
+      stage: Stage::default(),
+      variables: BTreeMap::new(),
+      history: VecDeque::new(),
+      finished: false,
+    }
+  }
+
+  /// continue from a saved cursor, with the stage as it was there
+  pub fn restore(entry: PathBuf, cursor: Cursor, stage: Stage) -> Self {
+    Self {
+      cursor: Some(cursor),
+      stage,
+      ..Self::new(entry)
+    }
+  }
+
+  pub fn with_config(mut self, config: PlayerConfig) -> Self {
+    self.config = config;
+    self
+  }
+
+  pub fn config(&self) -> &PlayerConfig {
+    &self.config
+  }
+
+  pub fn config_mut(&mut self) -> &mut PlayerConfig {
+    &mut self.config
+  }
+
+  pub fn entry(&self) -> &Path {
+    &self.entry
+  }
+
+  pub fn cursor(&self) -> Option<&Cursor> {
+    self.cursor.as_ref()
+  }
+
+  pub fn stage(&self) -> &Stage {
+    &self.stage
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  pub fn variables(&self) -> &BTreeMap<String, String> {
+    &self.variables
+  }
+
+  pub fn variables_mut(&mut self) -> &mut BTreeMap<String, String> {
+    &mut self.variables
+  }
+
+  /// whether skip mode can go on from the current step, stopping at choices,
+  /// `seen` tells whether the step had been seen before it was entered
+  pub fn can_skip(&self, loader: &dyn StoryLoader, seen: bool) -> bool {
+    self.cursor.is_some() && self.choice(loader).is_none() && (self.config.skip_unread || seen)
+  }
+
+  pub fn can_rollback(&self) -> bool {
+    self
+      .history
+      .back()
+      .is_some_and(|history| self.config.rollback_choices || !history.choice)
+  }
+
+  /// go back to the previous step, with the stage and the variables as they were there
+  pub fn rollback(&mut self) -> Result<(), PlayerError> {
+    let history = self.history.back().ok_or(PlayerError::NoHistory)?;
+    if history.choice && !self.config.rollback_choices {
+      return Err(PlayerError::RollbackAcrossChoice);
+    }
+
+    let history = self.history.pop_back().ok_or(PlayerError::NoHistory)?;
+    self.cursor = Some(history.cursor);
+    self.stage = history.stage;
+    self.variables = history.variables;
+    self.finished = false;
+    Ok(())
+  }
+
+  /// remember the current step before leaving it
+  fn push_history(&mut self, history: Option<History>) {
+    let Some(history) = history else {
+      return;
+    };
+
+    self.history.push_back(history);
+    while self.history.len() > self.config.rollback_depth {
+      self.history.pop_front();
+    }
+  }
+
+  fn current_history(&self, choice: bool) -> Option<History> {
+    Some(History {
+      cursor: self.cursor.clone()?,
+      stage: self.stage.clone(),
+      variables: self.variables.clone(),
+      choice,
+    })
+  }
+
+  pub fn current_step<'a>(&self, loader: &'a dyn StoryLoader) -> Option<&'a StoryStep> {
+    self.cursor.as_ref()?.step(loader)
+  }
+
+  /// options of the choice waiting in the current step
+  pub fn choice<'a>(&self, loader: &'a dyn StoryLoader) -> Option<&'a [ChoiceOption]> {
+    let step = self.current_step(loader)?;
+    find_choice(step).map(|(_, options)| options)
+  }
+
+  /// go to the next step, following navigations to other story files,
+  /// returns `None` once the story is finished
+  pub fn advance<'a>(
+    &mut self,
+    loader: &'a dyn StoryLoader,
+  ) -> Result<Option<&'a StoryStep>, PlayerError> {
+    let cursor = match &self.cursor {
+      None if self.finished => return Ok(None),
+      None => Cursor::new(self.entry.clone()),
+      Some(cursor) => {
+        let step = cursor
+          .step(loader)
+          .ok_or_else(|| PlayerError::InvalidCursor(cursor.path.clone()))?;
+
+        if find_choice(step).is_some() {
+          return Err(PlayerError::ChoicePending);
+        }
+
+        match find_navigation(step) {
+          Some(url) => {
+            let path = join_absolute_path(&cursor.path, url)
+              .ok_or_else(|| PlayerError::StoryNotFound(url.to_path_buf()))?;
+            Cursor::new(path)
+          }
+          None => {
+            let mut cursor = cursor.clone();
+            if let Some(frame) = cursor.frames.last_mut() {
+              frame.step += 1;
+            }
+            cursor
+          }
+        }
+      }
+    };
+
+    let history = self.current_history(false);
+    let step = self.enter(loader, cursor)?;
+    self.push_history(history);
+    Ok(step)
+  }
+
+  /// pick an option of the choice in the current step and go into its branch
+  pub fn choose<'a>(
+    &mut self,
+    loader: &'a dyn StoryLoader,
+    option: usize,
+  ) -> Result<Option<&'a StoryStep>, PlayerError> {
+    let mut cursor = self.cursor.clone().ok_or(PlayerError::NoChoice)?;
+    let step = cursor
+      .step(loader)
+      .ok_or_else(|| PlayerError::InvalidCursor(cursor.path.clone()))?;
+    let (action, options) = find_choice(step).ok_or(PlayerError::NoChoice)?;
+
+    if option >= options.len() {
+      return Err(PlayerError::InvalidOption(option));
+    }
+
+    if let Some(frame) = cursor.frames.last_mut() {
+      frame.choice = Some((action, option));
+    }
+    cursor.frames.push(Frame::new(0));
+
+    let history = self.current_history(true);
+    let step = self.enter(loader, cursor)?;
+    self.push_history(history);
+    Ok(step)
+  }
+
+  /// move to the cursor, leaving finished branches, and play the step there
+  fn enter<'a>(
+    &mut self,
+    loader: &'a dyn StoryLoader,
+    mut cursor: Cursor,
+  ) -> Result<Option<&'a StoryStep>, PlayerError> {
+    let story = loader
+      .load(&cursor.path)
+      .ok_or_else(|| PlayerError::StoryNotFound(cursor.path.clone()))?;
+
+    loop {
+      let steps = cursor
+        .steps(story)
+        .ok_or_else(|| PlayerError::InvalidCursor(cursor.path.clone()))?;
+      let index = cursor.frames.last().map_or(0, |x| x.step);
+
+      if let Some(step) = steps.get(index) {
+        if let Some(frame) = cursor.frames.last_mut() {
+          frame.id = step.id.clone();
+        }
+        for action in &step.actions {
+          self.stage.apply(&cursor.path, action);
+        }
+        self.cursor = Some(cursor);
+        return Ok(Some(step));
+      }
+
+      // the branch is over, continue after the choice
+      cursor.frames.pop();
+      match cursor.frames.last_mut() {
+        Some(frame) => {
+          frame.step += 1;
+          frame.choice = None;
+        }
+        None => {
+          self.cursor = None;
+          self.finished = true;
+          return Ok(None);
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+  use crate::{
+    story::{ChoiceOption, StepId, Story, StoryAction, StoryStep},
+    Location,
+  };
+
+  use super::{PlayerConfig, PlayerError, SeenSteps, StoryPlayer};
+
+  fn text(text: &str) -> StoryStep {
+    StoryStep {
+      id: StepId::new(text, 0, None),
+      actions: vec![StoryAction::Text {
+        name: None,
+        text: text.into(),
+        voice: None,
+      }],
+    }
+  }
+
+  fn bg(url: &str) -> StoryStep {
+    StoryStep {
+      id: StepId::default(),
+      actions: vec![StoryAction::Bg {
+        url: url.into(),
+        transition: None,
+        animation: None,
+        location: Location::default(),
+      }],
+    }
+  }
+
+  fn goto(url: &str) -> StoryStep {
+    StoryStep {
+      id: StepId::default(),
+      actions: vec![StoryAction::Navigate {
+        url: url.into(),
+        ret: false,
+      }],
+    }
+  }
+
+  fn choice(options: Vec<(&str, Vec<StoryStep>)>) -> StoryStep {
+    let options = options
+      .into_iter()
+      .map(|(text, steps)| ChoiceOption {
+        text: text.to_string(),
+        steps,
+      })
+      .collect();
+
+    StoryStep {
+      id: StepId::new("choice", 0, None),
+      actions: vec![StoryAction::Choice { options }],
+    }
+  }
+
+  #[test]
+  fn test_navigate() {
+    let stories = HashMap::from([
+      (
+        PathBuf::from("index.md"),
+        Story {
+          steps: vec![bg("./bg/room.png"), text("a"), goto("./chapter1/scene1.md")],
+        },
+      ),
+      (
+        PathBuf::from("chapter1/scene1.md"),
+        Story {
+          steps: vec![text("b"), bg("../bg/street.png")],
+        },
+      ),
+    ]);
+
+    let mut player = StoryPlayer::new("index.md".into());
+    assert_eq!(player.current_step(&stories), None);
+
+    player.advance(&stories).unwrap();
+    assert_eq!(
+      player.stage().bg.as_ref().map(|x| x.url.clone()),
+      Some(PathBuf::from("bg/room.png"))
+    );
+
+    assert_eq!(player.advance(&stories).unwrap(), Some(&text("a")));
+    player.advance(&stories).unwrap();
+    assert_eq!(player.advance(&stories).unwrap(), Some(&text("b")));
+    assert_eq!(
+      player.cursor().map(|x| x.path.clone()),
+      Some(PathBuf::from("chapter1/scene1.md"))
+    );
+
+    player.advance(&stories).unwrap();
+    assert_eq!(
+      player.stage().bg.as_ref().map(|x| x.url.clone()),
+      Some(PathBuf::from("bg/street.png"))
+    );
+
+    assert_eq!(player.advance(&stories), Ok(None));
+    assert!(player.is_finished());
+    assert_eq!(player.advance(&stories), Ok(None));
+  }
+
+  #[test]
+  fn test_choice() {
+    let stories = HashMap::from([(
+      PathBuf::from("index.md"),
+      Story {
+        steps: vec![
+          choice(vec![("yes", vec![text("a"), text("b")]), ("no", vec![])]),
+          text("c"),
+        ],
+      },
+    )]);
+
+    let mut player = StoryPlayer::new("index.md".into());
+    player.advance(&stories).unwrap();
+    assert_eq!(player.choice(&stories).map(|x| x.len()), Some(2));
+    assert_eq!(player.advance(&stories), Err(PlayerError::ChoicePending));
+    assert_eq!(
+      player.choose(&stories, 2),
+      Err(PlayerError::InvalidOption(2))
+    );
+
+    assert_eq!(player.choose(&stories, 0).unwrap(), Some(&text("a")));
+    assert_eq!(player.advance(&stories).unwrap(), Some(&text("b")));
+    assert_eq!(player.advance(&stories).unwrap(), Some(&text("c")));
+    assert_eq!(player.choose(&stories, 0), Err(PlayerError::NoChoice));
+
+    // an empty branch continues right after the choice
+    let mut player = StoryPlayer::new("index.md".into());
+    player.advance(&stories).unwrap();
+    assert_eq!(player.choose(&stories, 1).unwrap(), Some(&text("c")));
+
+    let mut player = StoryPlayer::new("missing.md".into());
+    assert_eq!(
+      player.advance(&stories),
+      Err(PlayerError::StoryNotFound("missing.md".into()))
+    );
+  }
+
+  #[test]
+  fn test_relocate() {
+    let story = |steps| HashMap::from([(PathBuf::from("index.md"), Story { steps })]);
+    let stories = story(vec![
+      text("a"),
+      choice(vec![("yes", vec![text("b"), text("c")])]),
+    ]);
+
+    let mut player = StoryPlayer::new("index.md".into());
+    player.advance(&stories).unwrap();
+    player.advance(&stories).unwrap();
+    player.choose(&stories, 0).unwrap();
+    player.advance(&stories).unwrap();
+    let cursor = player.cursor().unwrap().clone();
+
+    // paragraphs are written before the choice and into the branch
+    let edited = story(vec![
+      text("a"),
+      text("new"),
+      choice(vec![("yes", vec![text("b"), text("new"), text("c")])]),
+    ]);
+    let mut relocated = cursor.clone();
+    relocated.relocate(&edited).unwrap();
+    assert_eq!(relocated.step(&edited), Some(&text("c")));
+    assert_eq!(
+      relocated.frames.iter().map(|x| x.step).collect::<Vec<_>>(),
+      vec![2, 2]
+    );
+
+    let edited = story(vec![
+      text("a"),
+      choice(vec![("yes", vec![text("b"), text("d")])]),
+    ]);
+    let mut relocated = cursor;
+    assert_eq!(
+      relocated.relocate(&edited),
+      Err(PlayerError::StepNotFound("index.md".into()))
+    );
+  }
+
+  #[test]
+  fn test_rollback() {
+    let stories = HashMap::from([(
+      PathBuf::from("index.md"),
+      Story {
+        steps: vec![
+          bg("./a.png"),
+          bg("./b.png"),
+          choice(vec![("yes", vec![text("a")])]),
+          text("b"),
+        ],
+      },
+    )]);
+    let bg_url = |player: &StoryPlayer| player.stage().bg.as_ref().map(|x| x.url.clone());
+
+    let mut player = StoryPlayer::new("index.md".into());
+    assert_eq!(player.rollback(), Err(PlayerError::NoHistory));
+
+    player.advance(&stories).unwrap();
+    player.variables_mut().insert("x".into(), "1".into());
+    player.advance(&stories).unwrap();
+    player.variables_mut().insert("x".into(), "2".into());
+    assert_eq!(bg_url(&player), Some(PathBuf::from("b.png")));
+
+    player.rollback().unwrap();
+    assert_eq!(bg_url(&player), Some(PathBuf::from("a.png")));
+    assert_eq!(player.variables().get("x"), Some(&"1".to_string()));
+    assert_eq!(player.cursor().map(|x| x.frames[0].step), Some(0));
+    assert!(!player.can_rollback());
+
+    // the choice can be made again after rolling back
+    player.advance(&stories).unwrap();
+    player.advance(&stories).unwrap();
+    player.choose(&stories, 0).unwrap();
+    player.rollback().unwrap();
+    assert_eq!(player.choice(&stories).map(|x| x.len()), Some(1));
+
+    let config = PlayerConfig {
+      rollback_depth: 2,
+      rollback_choices: false,
+      ..Default::default()
+    };
+    let mut player = StoryPlayer::new("index.md".into()).with_config(config);
+    player.advance(&stories).unwrap();
+    player.advance(&stories).unwrap();
+    player.advance(&stories).unwrap();
+    player.choose(&stories, 0).unwrap();
+    assert!(!player.can_rollback());
+    assert_eq!(player.rollback(), Err(PlayerError::RollbackAcrossChoice));
+
+    player.advance(&stories).unwrap();
+    player.rollback().unwrap();
+    assert_eq!(player.current_step(&stories), Some(&text("a")));
+    assert_eq!(player.rollback(), Err(PlayerError::RollbackAcrossChoice));
+
+    // only the latest steps are kept
+    let mut player = StoryPlayer::new("index.md".into()).with_config(PlayerConfig {
+      rollback_depth: 1,
+      ..Default::default()
+    });
+    player.advance(&stories).unwrap();
+    player.advance(&stories).unwrap();
+    player.advance(&stories).unwrap();
+    player.rollback().unwrap();
+    assert_eq!(player.rollback(), Err(PlayerError::NoHistory));
+    assert_eq!(bg_url(&player), Some(PathBuf::from("b.png")));
+  }
+
+  #[test]
+  fn test_skip() {
+    let stories = HashMap::from([(
+      PathBuf::from("index.md"),
+      Story {
+        steps: vec![
+          text("a"),
+          text("b"),
+          choice(vec![("yes", vec![])]),
+          text("c"),
+        ],
+      },
+    )]);
+
+    let mut seen = SeenSteps::default();
+    let mut player = StoryPlayer::new("index.md".into());
+    assert!(!player.can_skip(&stories, true));
+
+    player.advance(&stories).unwrap();
+    assert!(seen.insert(player.cursor().unwrap()));
+    assert!(!seen.insert(player.cursor().unwrap()));
+    assert!(!player.can_skip(&stories, false));
+    assert!(player.can_skip(&stories, true));
+
+    player.advance(&stories).unwrap();
+    assert!(!seen.contains(player.cursor().unwrap()));
+    player.advance(&stories).unwrap();
+    assert!(!player.can_skip(&stories, true));
+
+    let mut player = StoryPlayer::new("index.md".into()).with_config(PlayerConfig {
+      skip_unread: true,
+      ..Default::default()
+    });
+    player.advance(&stories).unwrap();
+    player.advance(&stories).unwrap();
+    assert!(player.can_skip(&stories, false));
+    player.advance(&stories).unwrap();
+    assert!(!player.can_skip(&stories, false));
+  }
+
+  #[test]
+  fn test_seen_after_edit() {
+    let step = |source: &str, line| StoryStep {
+      id: StepId::new(source, line, None),
+      ..text(source)
+    };
+    let story = |steps| HashMap::from([(PathBuf::from("index.md"), Story { steps })]);
+    let stories = story(vec![step("a", 1), step("b", 3)]);
+
+    let mut seen = SeenSteps::default();
+    let mut player = StoryPlayer::new("index.md".into());
+    player.advance(&stories).unwrap();
+    player.advance(&stories).unwrap();
+    seen.insert(player.cursor().unwrap());
+
+    // a paragraph is written above, moving the lines of the steps
+    let edited = story(vec![step("new", 1), step("a", 3), step("b", 5)]);
+    let mut player = StoryPlayer::new("index.md".into());
+    player.advance(&edited).unwrap();
+    assert!(!seen.contains(player.cursor().unwrap()));
+    player.advance(&edited).unwrap();
+    player.advance(&edited).unwrap();
+    assert!(seen.contains(player.cursor().unwrap()));
+  }
+
+  #[test]
+  fn test_auto_delay() {
+    let config = PlayerConfig {
+      auto_delay: Duration::from_secs(1),
+      auto_char_delay: Duration::from_millis(100),
+      ..Default::default()
+    };
+
+    let text = "「你 好」".into();
+    assert_eq!(config.auto_delay(&text, None), Duration::from_millis(1400));
+    assert_eq!(
+      config.auto_delay(&text, Some(Duration::from_millis(200))),
+      Duration::from_millis(1400)
+    );
+    assert_eq!(
+      config.auto_delay(&text, Some(Duration::from_secs(2))),
+      Duration::from_secs(3)
+    );
+  }
+}
@@ -0,0 +1,366 @@
+use std::{
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  color::Color,
+  easing::EasingFunction,
+  location::Location,
+  path::join_absolute_path,
+  story::{
+    Animation, AnimationDirection, AnimationFill, AnimationIteration, AnimationType, Keyframe,
+    Story, StoryAction,
+  },
+};
+
+/// a background or a figure shown on the stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageImage {
+  /// resolved against the story file
+  pub url: PathBuf,
+  pub location: Location,
+  pub animation: Option<Animation>,
+}
+
+/// what stays on the stage after playing the steps so far
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stage {
+  pub bg: Option<StageImage>,
+  /// figures by name, in the order they showed up
+  pub figures: Vec<(String, StageImage)>,
+  /// resolved against the story file
+  pub bgm: Option<PathBuf>,
+}
+
+impl Stage {
+  pub fn figure(&self, name: &str) -> Option<&StageImage> {
+    self
+      .figures
+      .iter()
+      .find_map(|(x, image)| (x == name).then_some(image))
+  }
+
+  /// apply the lasting effects of an action from the story file `story`,
+  /// texts and sound effects leave nothing behind
+  pub fn apply(&mut self, story: &Path, action: &StoryAction) {
+    let resolve = |url: &Path| join_absolute_path(story, url).unwrap_or_else(|| url.to_path_buf());
+
+    match action {
+      StoryAction::Bg {
+        url,
+        animation,
+        location,
+        ..
+      } => {
+        self.bg = Some(StageImage {
+          url: resolve(url),
+          location: location.clone(),
+          animation: animation.clone(),
+        });
+      }
+      StoryAction::Fig {
+        name,
+        removal: true,
+        ..
+      } => {
+        self.figures.retain(|(x, _)| x != name);
+      }
+      StoryAction::Fig {
+        name,
+        url,
+        animation,
+        location,
+        ..
+      } => {
+        let image = StageImage {
+          url: resolve(url),
+          location: location.clone(),
+          animation: animation.clone(),
+        };
+        match self.figures.iter_mut().find(|(x, _)| x == name) {
+          Some((_, figure)) => *figure = image,
+          None => self.figures.push((name.clone(), image)),
+        }
+      }
+      StoryAction::Bgm { url } => self.bgm = Some(resolve(url)),
+      _ => {}
+    }
+  }
+}
+
+impl Stage {
+  /// what the stage looks like once every finite animation is over
+  pub fn snapshot(&self) -> StageSnapshot {
+    StageSnapshot {
+      bg: self.bg.as_ref().map(ImageSnapshot::new),
+      figures: self
+        .figures
+        .iter()
+        .map(|(name, image)| (name.clone(), ImageSnapshot::new(image)))
+        .collect(),
+      bgm: self.bgm.clone(),
+    }
+  }
+}
+
+/// an image on the stage with its animation resolved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageSnapshot {
+  pub url: PathBuf,
+  pub location: Location,
+  /// clockwise, in degrees
+  pub rotation: f32,
+  pub scale: f32,
+  pub opacity: f32,
+  /// blur radius in pixels
+  pub blur: f32,
+  pub tint: Color,
+  /// infinite animations never end, so they are kept to be played again
+  pub animation: Option<Animation>,
+}
+
+impl ImageSnapshot {
+  fn new(image: &StageImage) -> Self {
+    let mut snapshot = Self {
+      url: image.url.clone(),
+      location: image.location.clone(),
+      rotation: 0.0,
+      scale: 1.0,
+      opacity: 1.0,
+      blur: 0.0,
+      tint: Color::WHITE,
+      animation: None,
+    };
+
+    let Some(animation) = &image.animation else {
+      return snapshot;
+    };
+    let end = match animation.iteration {
+      AnimationIteration::Count(count) => animation.delay + animation.time.mul_f32(count),
+      AnimationIteration::Infinite => {
+        snapshot.animation = Some(animation.clone());
+        return snapshot;
+      }
+    };
+    // `None` when the animation does not fill forwards
+    let Some(progress) = animation.progress(end) else {
+      return snapshot;
+    };
+
+    match &animation.ty {
+      AnimationType::To { location } => {
+        snapshot.location = image.location.lerp(location, progress);
+      }
+      AnimationType::Keyframes(keyframes) => {
+        if let Some((index, progress)) = Keyframe::locate(keyframes, progress) {
+          let from = keyframes[..index]
+            .iter()
+            .rev()
+            .find_map(|x| x.location.as_ref())
+            .unwrap_or(&image.location);
+          let to = keyframes[index].location.as_ref().unwrap_or(from);
+          snapshot.location = from.lerp(to, progress);
+        }
+      }
+      AnimationType::Shake => {}
+      AnimationType::Rotate { degrees } => snapshot.rotation = degrees * progress,
+      AnimationType::Scale { factor } => snapshot.scale = 1.0 + (factor - 1.0) * progress,
+      AnimationType::Opacity { opacity } => snapshot.opacity = 1.0 + (opacity - 1.0) * progress,
+      AnimationType::Blur { radius } => snapshot.blur = radius * progress,
+      AnimationType::Tint { color } => snapshot.tint = Color::WHITE.lerp(color, progress),
+    }
+
+    snapshot
+  }
+
+  /// the image resting where the snapshot left it, with an instant animation
+  /// for the effect if there is one
+  pub fn to_image(&self) -> StageImage {
+    let effect = match () {
+      _ if self.animation.is_some() => None,
+      _ if self.rotation != 0.0 => Some(AnimationType::Rotate {
+        degrees: self.rotation,
+      }),
+      _ if self.scale != 1.0 => Some(AnimationType::Scale { factor: self.scale }),
+      _ if self.opacity != 1.0 => Some(AnimationType::Opacity {
+        opacity: self.opacity,
+      }),
+      _ if self.blur != 0.0 => Some(AnimationType::Blur { radius: self.blur }),
+      _ if self.tint != Color::WHITE => Some(AnimationType::Tint { color: self.tint }),
+      _ => None,
+    };
+
+    let animation = effect.map(|ty| Animation {
+      ty,
+      time: Duration::ZERO,
+      easing: EasingFunction::linear(),
+      delay: Duration::ZERO,
+      iteration: AnimationIteration::Count(1.0),
+      direction: AnimationDirection::Normal,
+      fill: AnimationFill::Forwards,
+    });
+
+    StageImage {
+      url: self.url.clone(),
+      location: self.location.clone(),
+      animation: animation.or_else(|| self.animation.clone()),
+    }
+  }
+}
+
+/// everything visible and playing at some point of the story, for saving and skipping
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StageSnapshot {
+  pub bg: Option<ImageSnapshot>,
+  /// figures by name, in the order they showed up
+  pub figures: Vec<(String, ImageSnapshot)>,
+  pub bgm: Option<PathBuf>,
+}
+
+impl StageSnapshot {
+  /// the stage right before the step `step` of the story file `path`,
+  /// branches of choices are not taken into account
+  pub fn at(story: &Story, path: &Path, step: usize) -> Self {
+    let mut stage = Stage::default();
+    for action in story.steps.iter().take(step).flat_map(|x| &x.actions) {
+      stage.apply(path, action);
+    }
+    stage.snapshot()
+  }
+}
+
+impl From<&StageSnapshot> for Stage {
+  fn from(snapshot: &StageSnapshot) -> Self {
+    Self {
+      bg: snapshot.bg.as_ref().map(ImageSnapshot::to_image),
+      figures: snapshot
+        .figures
+        .iter()
+        .map(|(name, image)| (name.clone(), image.to_image()))
+        .collect(),
+      bgm: snapshot.bgm.clone(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{path::PathBuf, time::Duration};
+
+  use crate::{
+    easing::EasingFunction,
+    story::{
+      Animation, AnimationDirection, AnimationFill, AnimationIteration, AnimationType, StepId,
+      Story, StoryAction, StoryStep,
+    },
+    Location, Position, Size,
+  };
+
+  use super::{Stage, StageSnapshot};
+
+  fn animation(ty: AnimationType, iteration: AnimationIteration) -> Option<Animation> {
+    Some(Animation {
+      ty,
+      time: Duration::from_secs(2),
+      easing: EasingFunction::linear(),
+      delay: Duration::ZERO,
+      iteration,
+      direction: AnimationDirection::Normal,
+      fill: AnimationFill::Forwards,
+    })
+  }
+
+  fn fig(name: &str, removal: bool, animation: Option<Animation>) -> StoryAction {
+    StoryAction::Fig {
+      name: name.to_string(),
+      url: format!("./{name}.png").into(),
+      transition: None,
+      animation,
+      location: Location::default(),
+      removal,
+    }
+  }
+
+  #[test]
+  fn test_snapshot() {
+    let target = Location {
+      position: Position(1.0, 0.5),
+      size: Size::Cover,
+    };
+    let story = Story {
+      steps: vec![
+        StoryStep {
+          id: StepId::default(),
+          actions: vec![
+            StoryAction::Bg {
+              url: "./bg.png".into(),
+              transition: None,
+              animation: animation(
+                AnimationType::To {
+                  location: target.clone(),
+                },
+                AnimationIteration::Count(1.0),
+              ),
+              location: Location::default(),
+            },
+            StoryAction::Bgm {
+              url: "./bgm.mp3".into(),
+            },
+          ],
+        },
+        StoryStep {
+          id: StepId::default(),
+          actions: vec![
+            fig(
+              "alice",
+              false,
+              animation(
+                AnimationType::Rotate { degrees: 90.0 },
+                AnimationIteration::Count(0.5),
+              ),
+            ),
+            fig(
+              "bob",
+              false,
+              animation(
+                AnimationType::Opacity { opacity: 0.5 },
+                AnimationIteration::Infinite,
+              ),
+            ),
+          ],
+        },
+        StoryStep {
+          id: StepId::default(),
+          actions: vec![fig("alice", true, None)],
+        },
+      ],
+    };
+    let path = PathBuf::from("story/index.md");
+
+    let snapshot = StageSnapshot::at(&story, &path, 2);
+    let bg = snapshot.bg.as_ref().unwrap();
+    assert_eq!(bg.url, PathBuf::from("story/bg.png"));
+    assert_eq!(bg.location, target);
+    assert_eq!(bg.animation, None);
+    assert_eq!(snapshot.bgm, Some(PathBuf::from("story/bgm.mp3")));
+
+    let (alice, bob) = (&snapshot.figures[0], &snapshot.figures[1]);
+    assert_eq!(alice.0, "alice");
+    assert_eq!(alice.1.rotation, 45.0);
+    assert_eq!(bob.1.opacity, 1.0);
+    assert!(bob.1.animation.is_some());
+    assert_eq!(Stage::from(&snapshot).snapshot(), snapshot);
+
+    let snapshot = StageSnapshot::at(&story, &path, 3);
+    assert_eq!(snapshot.figures.len(), 1);
+    assert_eq!(snapshot.figures[0].0, "bob");
+
+    assert_eq!(
+      StageSnapshot::at(&story, &path, 0),
+      StageSnapshot::default()
+    );
+  }
+}
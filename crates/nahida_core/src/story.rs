@@ -1,12 +1,64 @@
 use std::{path::PathBuf, time::Duration};
 
-use crate::{easing::EasingFunction, location::Location};
+use serde::{Deserialize, Serialize};
+
+use crate::{color::Color, easing::EasingFunction, location::Location, text::RichText};
 
 #[derive(Debug, PartialEq)]
 pub struct StoryStep {
+  pub id: StepId,
   pub actions: Vec<StoryAction>,
 }
 
+/// identifies a step across edits of the script
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StepId {
+  /// name given with `[anchor](#name)`
+  pub anchor: Option<String>,
+  /// line in the source where the step starts
+  pub line: usize,
+  /// hash of the source of the step, ignoring whitespaces
+  pub hash: u64,
+}
+
+impl StepId {
+  pub fn new(source: &str, line: usize, anchor: Option<String>) -> Self {
+    Self {
+      anchor,
+      line,
+      hash: fnv1a(source.split_whitespace()),
+    }
+  }
+
+  /// find the step in the (possibly edited) steps, by the anchor first,
+  /// then by the step with the same source closest to the original line
+  pub fn locate(&self, steps: &[StoryStep]) -> Option<usize> {
+    let anchored = self.anchor.as_ref().and_then(|anchor| {
+      steps
+        .iter()
+        .position(|step| step.id.anchor.as_ref() == Some(anchor))
+    });
+
+    anchored.or_else(|| {
+      steps
+        .iter()
+        .enumerate()
+        .filter(|(_, step)| step.id.hash == self.hash)
+        .min_by_key(|(_, step)| step.id.line.abs_diff(self.line))
+        .map(|(index, _)| index)
+    })
+  }
+}
+
+/// stable across runs and platforms, unlike the std hasher
+fn fnv1a<'a>(words: impl Iterator<Item = &'a str>) -> u64 {
+  words
+    .flat_map(|word| word.bytes().chain(Some(b' ')))
+    .fold(0xcbf29ce484222325, |hash, byte| {
+      (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Story {
   /// runtime steps
@@ -19,7 +71,12 @@ pub enum StoryAction {
   Wait { time: Duration },
 
   /// showing a text
-  Text { name: Option<String>, text: String },
+  Text {
+    name: Option<String>,
+    text: RichText,
+    /// voice played along with the text
+    voice: Option<PathBuf>,
+  },
 
   /// changing the background
   Bg {
@@ -47,9 +104,20 @@ pub enum StoryAction {
 
   /// control flow
   Navigate { url: PathBuf, ret: bool },
+
+  /// asking the player to pick one of the options
+  Choice { options: Vec<ChoiceOption> },
 }
 
 #[derive(Debug, PartialEq)]
+pub struct ChoiceOption {
+  /// text shown to the player
+  pub text: String,
+  /// steps to run after picking this option
+  pub steps: Vec<StoryStep>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum TransitionType {
   FadeIn,
   FadeOut,
@@ -58,24 +126,298 @@ pub enum TransitionType {
   BlindsIn,
   BlindsOut,
   Shake,
+  /// wipe in where the grayscale mask is darker than the progress
+  Rule {
+    mask: PathBuf,
+    /// width of the soft edge, from 0 to 1
+    softness: f32,
+  },
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Transition {
+  /// `Shake` when the image only shakes
   pub ty: TransitionType,
   pub time: Duration,
   pub easing: EasingFunction,
+  /// shake the image while the transition plays
+  pub shake: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AnimationType {
-  To { location: Location },
+  To {
+    location: Location,
+  },
   Shake,
+  /// segments played one after another
+  Keyframes(Vec<Keyframe>),
+  /// rotate clockwise by degrees
+  Rotate {
+    degrees: f32,
+  },
+  /// scale uniformly around the center
+  Scale {
+    factor: f32,
+  },
+  /// fade to the opacity, from 0 to 1
+  Opacity {
+    opacity: f32,
+  },
+  /// gaussian blur with the radius in pixels
+  Blur {
+    radius: f32,
+  },
+  /// multiply the image with the color
+  Tint {
+    color: Color,
+  },
 }
 
-#[derive(Debug, PartialEq)]
+/// a segment of a keyframe animation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+  /// where to move to, `None` holds the previous location
+  pub location: Option<Location>,
+  pub time: Duration,
+  pub easing: EasingFunction,
+}
+
+impl Keyframe {
+  /// find the keyframe playing at `progress` of the whole animation,
+  /// along with its own eased progress
+  pub fn locate(keyframes: &[Keyframe], progress: f32) -> Option<(usize, f32)> {
+    let total = keyframes.iter().map(|x| x.time.as_secs_f32()).sum::<f32>();
+    let mut elapsed = progress.clamp(0.0, 1.0) * total;
+
+    for (index, keyframe) in keyframes.iter().enumerate() {
+      let time = keyframe.time.as_secs_f32();
+      if elapsed < time || index + 1 == keyframes.len() {
+        let local = match time {
+          _ if time > 0.0 => (elapsed / time).min(1.0),
+          _ => 1.0,
+        };
+        return Some((index, keyframe.easing.easing(local)));
+      }
+      elapsed -= time;
+    }
+
+    None
+  }
+}
+
+/// how many times an animation plays
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AnimationIteration {
+  /// may be fractional, `0.5` stops halfway
+  Count(f32),
+  Infinite,
+}
+
+impl Default for AnimationIteration {
+  fn default() -> Self {
+    Self::Count(1.0)
+  }
+}
+
+/// which way each iteration plays
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnimationDirection {
+  #[default]
+  Normal,
+  Reverse,
+  /// forwards on even iterations and backwards on odd ones
+  Alternate,
+  /// backwards on even iterations and forwards on odd ones
+  AlternateReverse,
+}
+
+/// whether the animation applies before its delay and after it ends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnimationFill {
+  None,
+  /// keep the end state, so that images stay where they moved to
+  #[default]
+  Forwards,
+  Backwards,
+  Both,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Animation {
   pub ty: AnimationType,
+  /// duration of a single iteration
   pub time: Duration,
   pub easing: EasingFunction,
+  pub delay: Duration,
+  pub iteration: AnimationIteration,
+  pub direction: AnimationDirection,
+  pub fill: AnimationFill,
+}
+
+impl Animation {
+  /// eased progress of the animation `elapsed` after it was started,
+  /// `None` if the animation does not apply at that time
+  pub fn progress(&self, elapsed: Duration) -> Option<f32> {
+    let backwards = matches!(self.fill, AnimationFill::Backwards | AnimationFill::Both);
+    let forwards = matches!(self.fill, AnimationFill::Forwards | AnimationFill::Both);
+
+    let (iteration, progress) = match elapsed.checked_sub(self.delay) {
+      None if backwards => (0.0, 0.0),
+      None => return None,
+      Some(active) => {
+        let active = active.as_secs_f32();
+        let time = self.time.as_secs_f32();
+        let total = match self.iteration {
+          AnimationIteration::Count(count) => time * count,
+          AnimationIteration::Infinite => f32::INFINITY,
+        };
+
+        match () {
+          _ if active < total && time > 0.0 => {
+            let iteration = (active / time).floor();
+            (iteration, active / time - iteration)
+          }
+          _ if !forwards => return None,
+          // stop at the end of the last iteration, or somewhere in it for fractional counts
+          _ => {
+            let count = match self.iteration {
+              AnimationIteration::Count(count) => count,
+              AnimationIteration::Infinite => 1.0,
+            };
+            match count.fract() {
+              fract if fract > 0.0 || count == 0.0 => (count.floor(), fract),
+              _ => (count - 1.0, 1.0),
+            }
+          }
+        }
+      }
+    };
+
+    let odd = iteration % 2.0 == 1.0;
+    let reversed = match self.direction {
+      AnimationDirection::Normal => false,
+      AnimationDirection::Reverse => true,
+      AnimationDirection::Alternate => odd,
+      AnimationDirection::AlternateReverse => !odd,
+    };
+    let progress = if reversed { 1.0 - progress } else { progress };
+
+    Some(self.easing.easing(progress))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use crate::easing::EasingFunction;
+
+  use super::{
+    Animation, AnimationDirection, AnimationFill, AnimationIteration, AnimationType, Keyframe,
+    StepId, StoryStep,
+  };
+
+  fn animation(
+    iteration: AnimationIteration,
+    direction: AnimationDirection,
+    fill: AnimationFill,
+  ) -> Animation {
+    Animation {
+      ty: AnimationType::Shake,
+      time: Duration::from_secs(2),
+      easing: EasingFunction::linear(),
+      delay: Duration::from_secs(1),
+      iteration,
+      direction,
+      fill,
+    }
+  }
+
+  #[test]
+  fn test_progress() {
+    let normal = animation(
+      AnimationIteration::Count(1.0),
+      AnimationDirection::Normal,
+      AnimationFill::Forwards,
+    );
+    assert_eq!(normal.progress(Duration::from_millis(500)), None);
+    assert_eq!(normal.progress(Duration::from_secs(2)), Some(0.5));
+    assert_eq!(normal.progress(Duration::from_secs(10)), Some(1.0));
+
+    let alternate = animation(
+      AnimationIteration::Infinite,
+      AnimationDirection::Alternate,
+      AnimationFill::None,
+    );
+    assert_eq!(alternate.progress(Duration::from_millis(1500)), Some(0.25));
+    assert_eq!(alternate.progress(Duration::from_millis(3500)), Some(0.75));
+    assert_eq!(alternate.progress(Duration::from_millis(5500)), Some(0.25));
+
+    let reverse = animation(
+      AnimationIteration::Count(2.5),
+      AnimationDirection::AlternateReverse,
+      AnimationFill::Both,
+    );
+    assert_eq!(reverse.progress(Duration::ZERO), Some(1.0));
+    assert_eq!(reverse.progress(Duration::from_secs(4)), Some(0.5));
+    assert_eq!(reverse.progress(Duration::from_secs(10)), Some(0.5));
+
+    let none = animation(
+      AnimationIteration::Count(1.0),
+      AnimationDirection::Reverse,
+      AnimationFill::None,
+    );
+    assert_eq!(none.progress(Duration::from_secs(2)), Some(0.5));
+    assert_eq!(none.progress(Duration::from_secs(3)), None);
+  }
+
+  #[test]
+  fn test_keyframes() {
+    let keyframe = |secs, easing| Keyframe {
+      location: None,
+      time: Duration::from_secs(secs),
+      easing,
+    };
+    let keyframes = [
+      keyframe(1, EasingFunction::linear()),
+      keyframe(2, EasingFunction::step_end()),
+      keyframe(1, EasingFunction::linear()),
+    ];
+
+    assert_eq!(Keyframe::locate(&keyframes, 0.0), Some((0, 0.0)));
+    assert_eq!(Keyframe::locate(&keyframes, 0.125), Some((0, 0.5)));
+    assert_eq!(Keyframe::locate(&keyframes, 0.5), Some((1, 0.0)));
+    assert_eq!(Keyframe::locate(&keyframes, 0.875), Some((2, 0.5)));
+    assert_eq!(Keyframe::locate(&keyframes, 1.0), Some((2, 1.0)));
+    assert_eq!(Keyframe::locate(&[], 0.5), None);
+  }
+
+  #[test]
+  fn test_step_id() {
+    let step = |source: &str, line, anchor: Option<&str>| StoryStep {
+      id: StepId::new(source, line, anchor.map(String::from)),
+      actions: vec![],
+    };
+
+    let saved = StepId::new("hello  world", 3, None);
+    assert_eq!(saved, StepId::new("hello\nworld", 3, None));
+
+    // a paragraph inserted before, and the same text later on
+    let steps = [
+      step("new", 1, None),
+      step("hello world", 5, None),
+      step("other", 7, None),
+      step("hello world", 20, None),
+    ];
+    assert_eq!(saved.locate(&steps), Some(1));
+    assert_eq!(saved.locate(&steps[2..]), Some(1));
+    assert_eq!(saved.locate(&steps[2..3]), None);
+
+    // the anchored text is rewritten
+    let saved = StepId::new("hello", 3, Some("start".into()));
+    let steps = [step("hello", 1, None), step("hi", 3, Some("start"))];
+    assert_eq!(saved.locate(&steps), Some(1));
+    assert_eq!(saved.locate(&steps[..1]), Some(0));
+  }
 }
@@ -0,0 +1,178 @@
+use std::{fmt, path::PathBuf, time::Duration};
+
+use crate::Color;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextStyle {
+  /// `**strong**`
+  pub bold: bool,
+  /// `*emphasis*`
+  pub italic: bool,
+  /// `` `code` ``, rendered with a highlight color
+  pub code: bool,
+  /// `[text](#rrggbb)`
+  pub color: Option<Color>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextSpan {
+  /// a piece of styled text
+  Text { text: String, style: TextStyle },
+  /// hard line break
+  Break,
+  /// stop typing for a while, `[pause](#ms)`
+  Pause(Duration),
+  /// type the rest faster or slower by the factor, `[speed](#factor)`
+  Speed(f32),
+  /// play the sound once typed here, `[sfx](url)`
+  Sfx(PathBuf),
+}
+
+impl TextSpan {
+  pub fn plain(text: &str) -> Self {
+    Self::Text {
+      text: text.to_string(),
+      style: TextStyle::default(),
+    }
+  }
+}
+
+/// text made of styled spans
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RichText {
+  pub spans: Vec<TextSpan>,
+}
+
+impl RichText {
+  pub fn new(spans: Vec<TextSpan>) -> Self {
+    Self { spans }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.spans.is_empty()
+  }
+
+  /// sounds cued in the text
+  pub fn sfx(&self) -> impl Iterator<Item = &PathBuf> {
+    self.spans.iter().filter_map(|span| match span {
+      TextSpan::Sfx(url) => Some(url),
+      _ => None,
+    })
+  }
+
+  /// the part typed after `elapsed`, at `speed` characters per second
+  pub fn typed(&self, elapsed: Duration, speed: f32) -> RichText {
+    self.typing(elapsed, speed).0
+  }
+
+  /// time to type the whole text at `speed` characters per second
+  pub fn typing_time(&self, speed: f32) -> Duration {
+    self.typing(Duration::ZERO, speed).1
+  }
+
+  fn typing(&self, elapsed: Duration, speed: f32) -> (RichText, Duration) {
+    let elapsed = elapsed.as_secs_f64();
+    // when the span starts to be typed
+    let mut time = 0.0;
+    let mut factor = 1.0;
+    let mut typed = Vec::new();
+
+    for span in &self.spans {
+      let reached = time <= elapsed;
+
+      match span {
+        TextSpan::Text { text, style } => {
+          let rate = speed as f64 * factor;
+          let interval = if rate > 0.0 { 1.0 / rate } else { 0.0 };
+          let end = time + text.chars().count() as f64 * interval;
+
+          if elapsed >= end {
+            typed.push(span.clone());
+          } else if reached {
+            let count = ((elapsed - time) / interval) as usize;
+            if count > 0 {
+              typed.push(TextSpan::Text {
+                text: text.chars().take(count).collect(),
+                style: style.clone(),
+              });
+            }
+          }
+          time = end;
+          continue;
+        }
+        TextSpan::Pause(pause) => time += pause.as_secs_f64(),
+        TextSpan::Speed(speed) => factor = *speed as f64,
+        TextSpan::Break | TextSpan::Sfx(_) => {}
+      }
+
+      if reached {
+        typed.push(span.clone());
+      }
+    }
+
+    (RichText::new(typed), Duration::from_secs_f64(time))
+  }
+}
+
+impl From<&str> for RichText {
+  fn from(text: &str) -> Self {
+    Self::new(vec![TextSpan::plain(text)])
+  }
+}
+
+/// writes the text without any style
+impl fmt::Display for RichText {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for span in &self.spans {
+      match span {
+        TextSpan::Text { text, .. } => f.write_str(text)?,
+        TextSpan::Break => f.write_str("\n")?,
+        TextSpan::Pause(_) | TextSpan::Speed(_) | TextSpan::Sfx(_) => {}
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::{RichText, TextSpan};
+
+  #[test]
+  fn test_typed() {
+    let text = RichText::new(vec![
+      TextSpan::plain("一二"),
+      TextSpan::Pause(Duration::from_secs(1)),
+      TextSpan::Sfx("./ding.mp3".into()),
+      TextSpan::Speed(2.0),
+      TextSpan::plain("三四"),
+    ]);
+    let typed = |secs: f64| text.typed(Duration::from_secs_f64(secs), 1.0).spans;
+
+    assert_eq!(text.typing_time(1.0), Duration::from_secs(4));
+    assert_eq!(typed(0.0), vec![]);
+    assert_eq!(typed(1.5), vec![TextSpan::plain("一")]);
+    assert_eq!(
+      typed(2.5),
+      vec![
+        TextSpan::plain("一二"),
+        TextSpan::Pause(Duration::from_secs(1))
+      ]
+    );
+    assert_eq!(
+      typed(3.6),
+      [&text.spans[..4], &[TextSpan::plain("三")]].concat()
+    );
+    assert_eq!(typed(4.0), text.spans);
+    assert_eq!(text.to_string(), "一二三四");
+
+    // the text comes out at once without a speed
+    assert_eq!(text.typing_time(f32::INFINITY), Duration::from_secs(1));
+    assert_eq!(
+      text.typed(Duration::ZERO, f32::INFINITY).spans,
+      text.spans[..2]
+    );
+  }
+}
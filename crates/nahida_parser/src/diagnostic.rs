@@ -0,0 +1,246 @@
+//! Renders diagnostics for humans, with the offending source line and a hint
+//!
+
+use std::fmt::Write;
+
+use crate::parser::{
+  functions, keywords, step_positions, Diagnostic, ParseErrorType, IMAGES, LINKS,
+};
+
+impl ParseErrorType {
+  /// a suggestion on how to fix the error
+  pub fn hint(&self) -> Option<String> {
+    match self {
+      ParseErrorType::UnknownLink(word) => suggest(word, LINKS),
+      ParseErrorType::UnknownImage(word) => suggest(word, IMAGES),
+      ParseErrorType::InvalidHeading => {
+        Some("headings should only contain the speaker name".into())
+      }
+      ParseErrorType::NoFigureName => {
+        Some("put the name at the start of the title, e.g. `\"nahida left\"`".into())
+      }
+      ParseErrorType::InvalidWaitTime(_) => {
+        Some("wait time is in milliseconds, e.g. `[wait](#2000)`".into())
+      }
+      ParseErrorType::InvalidPauseTime(_) => {
+        Some("pause time is in milliseconds, e.g. `[pause](#500)`".into())
+      }
+      ParseErrorType::InvalidSpeed(_) => {
+        Some("speed is a positive factor of the text speed, e.g. `[speed](#0.5)`".into())
+      }
+      ParseErrorType::InvalidAnchor(_) => Some("name the anchor like `[anchor](#start)`".into()),
+      ParseErrorType::DuplicateAnchor(_) => {
+        Some("anchors should be unique in a story file".into())
+      }
+      ParseErrorType::OrphanVoice => Some("put the voice right before a text".into()),
+      ParseErrorType::DuplicateVoice => Some("split the text into one paragraph per voice".into()),
+      ParseErrorType::InvalidChoice => Some("options should only contain plain text".into()),
+      ParseErrorType::ChoiceBranchMismatch { .. } => {
+        Some("every option needs exactly one branch".into())
+      }
+      ParseErrorType::BranchWithoutChoice => {
+        Some("put an unordered list of options right before the branches".into())
+      }
+      ParseErrorType::MissingSizeWords => Some("remove the `/` or add a size, e.g. `cover`".into()),
+      ParseErrorType::UnknownToken(word) => suggest(word, &keywords()),
+      ParseErrorType::ConflictingTransition(..) => {
+        Some("only `shake` can go along with other transitions".into())
+      }
+      ParseErrorType::ConflictingAnimation(..) => Some("keep only one animation".into()),
+      ParseErrorType::InvalidKeyframe => {
+        Some("join `to <location>` or `wait` segments with `then`".into())
+      }
+      ParseErrorType::InvalidPosition(_) => Some("try something like `left 20% top`".into()),
+      ParseErrorType::InvalidSize(_) => Some("try something like `cover` or `30% auto`".into()),
+      ParseErrorType::InvalidFunction(word) => suggest_function(word).or_else(|| {
+        Some("try something like `cubic-bezier(0.1, 0.7, 1.0, 0.1)`, `steps(4, jump-end)` or `linear(0, 0.25 75%, 1)`".into())
+      }),
+      _ => None,
+    }
+  }
+}
+
+/// find the closest keyword to a misspelled word
+fn suggest(word: &str, candidates: &[&str]) -> Option<String> {
+  candidates
+    .iter()
+    .map(|candidate| (levenshtein(word, candidate), candidate))
+    .filter(|(distance, candidate)| *distance <= candidate.len().max(3) / 3 + 1)
+    .min_by_key(|(distance, _)| *distance)
+    .map(|(_, candidate)| format!("did you mean `{candidate}`?"))
+}
+
+/// a misspelled function name, or a misspelled step position in `steps()`
+fn suggest_function(word: &str) -> Option<String> {
+  let (name, args) = word.strip_suffix(')')?.split_once('(')?;
+  if !functions().contains(&name) {
+    return suggest(name, &functions());
+  }
+
+  let position = args.split(',').nth(1)?.trim();
+  match name == "steps" && !step_positions().contains(&position) {
+    true => suggest(position, &step_positions()),
+    false => None,
+  }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+  let b = b.chars().collect::<Vec<_>>();
+  let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+  for (i, x) in a.chars().enumerate() {
+    let mut prev = row[0];
+    row[0] = i + 1;
+    for (j, y) in b.iter().enumerate() {
+      let current = row[j + 1];
+      row[j + 1] = if x == *y {
+        prev
+      } else {
+        prev.min(row[j]).min(row[j + 1]) + 1
+      };
+      prev = current;
+    }
+  }
+
+  row[b.len()]
+}
+
+/// columns taken by the text in a terminal
+fn display_width(text: &str) -> usize {
+  text
+    .chars()
+    .map(|c| match c as u32 {
+      0x1100..=0x115f
+      | 0x2e80..=0xa4cf
+      | 0xac00..=0xd7a3
+      | 0xf900..=0xfaff
+      | 0xfe30..=0xfe4f
+      | 0xff00..=0xff60
+      | 0xffe0..=0xffe6
+      | 0x20000..=0x3fffd => 2,
+      _ => 1,
+    })
+    .sum()
+}
+
+impl Diagnostic {
+  /// render the diagnostic like
+  ///
+  /// ```text
+  /// error: unknown image: bgg
+  ///  --> story.md:5:1
+  ///   |
+  /// 5 | ![bgg](./background.png)
+  ///   | ^^^^^^^^^^^^^^^^^^^^^^^^
+  ///   = hint: did you mean `bg`?
+  /// ```
+  pub fn render(&self, file_name: &str, source: &str) -> String {
+    let ty = self.error.ty();
+    let mut output = format!("{}: {ty}\n", self.severity);
+
+    let Some(position) = self.error.position() else {
+      let _ = writeln!(output, " --> {file_name}");
+      if let Some(hint) = ty.hint() {
+        let _ = writeln!(output, "  = hint: {hint}");
+      }
+      return output;
+    };
+
+    let (start, end) = (&position.start, &position.end);
+    let line_start = source[..start.offset].rfind('\n').map_or(0, |x| x + 1);
+    let line_end = source[start.offset..]
+      .find('\n')
+      .map_or(source.len(), |x| start.offset + x);
+    let line = source[line_start..line_end].trim_end_matches('\r');
+
+    let prefix = &source[line_start..start.offset];
+    let column = prefix.chars().count() + 1;
+    let indent = display_width(prefix);
+    let underline = display_width(&source[start.offset..end.offset.clamp(start.offset, line_end)]);
+
+    let number = start.line.to_string();
+    let gutter = " ".repeat(number.len());
+
+    let _ = writeln!(output, "{gutter}--> {file_name}:{}:{column}", start.line);
+    let _ = writeln!(output, "{gutter} |");
+    let _ = writeln!(output, "{number} | {line}");
+    let _ = writeln!(
+      output,
+      "{gutter} | {}{}",
+      " ".repeat(indent),
+      "^".repeat(underline.max(1))
+    );
+    if let Some(hint) = ty.hint() {
+      let _ = writeln!(output, "{gutter} = hint: {hint}");
+    }
+
+    output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::parser::{functions, keywords, step_positions, NahidaParser, ParseErrorType};
+
+  use super::{display_width, levenshtein, suggest};
+
+  #[test]
+  fn test_suggest() {
+    assert_eq!(levenshtein("bgg", "bg"), 1);
+    assert_eq!(levenshtein("fade-inn", "fade-in"), 1);
+    assert_eq!(levenshtein("", "goto"), 4);
+
+    assert_eq!(
+      suggest("bgg", &["bg", "fig"]),
+      Some("did you mean `bg`?".into())
+    );
+    assert_eq!(
+      suggest("gogo", &["goto", "end"]),
+      Some("did you mean `goto`?".into())
+    );
+    assert_eq!(suggest("nahida", &["goto", "end"]), None);
+
+    assert_eq!(display_width("ab"), 2);
+    assert_eq!(display_width("「你好」"), 8);
+  }
+
+  #[test]
+  fn test_suggest_keywords() {
+    let hint = |word: &str| Some(format!("did you mean `{word}`?"));
+    // typed the first letter twice
+    let typo = |word: &str| format!("{}{word}", &word[..1]);
+
+    for word in keywords() {
+      let ty = ParseErrorType::UnknownToken(typo(word));
+      assert_eq!(ty.hint(), hint(word), "{word}");
+    }
+    for word in functions() {
+      let ty = ParseErrorType::InvalidFunction(format!("{}(1)", typo(word)));
+      assert_eq!(ty.hint(), hint(word), "{word}");
+    }
+    for word in step_positions() {
+      let ty = ParseErrorType::InvalidFunction(format!("steps(4, {})", typo(word)));
+      assert_eq!(ty.hint(), hint(word), "{word}");
+    }
+  }
+
+  #[test]
+  fn test_render() {
+    let source = "# 纳西妲\n\n「你好」![bgg](./background.png)\n";
+    let output = NahidaParser::default().parse(source);
+
+    assert_eq!(
+      output.diagnostics[0].render("story.md", source),
+      [
+        "error: unknown image: bgg",
+        " --> story.md:3:5",
+        "  |",
+        "3 | 「你好」![bgg](./background.png)",
+        "  |         ^^^^^^^^^^^^^^^^^^^^^^^^",
+        "  = hint: did you mean `bg`?",
+        "",
+      ]
+      .join("\n")
+    );
+  }
+}
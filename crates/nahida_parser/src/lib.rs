@@ -1,16 +1,34 @@
 use anyhow::anyhow;
+use log::{error, warn};
 use nahida_core::story::Story;
-use parser::NahidaParser;
 
+pub mod diagnostic;
 mod parser;
 
+pub use parser::{Diagnostic, NahidaParser, ParseError, ParseErrorType, ParseOutput, Severity};
+
 #[cfg(test)]
 mod tests;
 
+/// parse the script, returning the best-effort story with all the diagnostics
+pub fn parse_story_with_diagnostics(text: &str) -> ParseOutput {
+  NahidaParser::default().parse(text)
+}
+
 pub fn parse_story(text: &str) -> anyhow::Result<Story> {
-  let story = NahidaParser::default()
-    .parse_text(text)
-    .map_err(|err| anyhow!("failed to parse: {err:?}"))?;
+  let output = parse_story_with_diagnostics(text);
+
+  for diagnostic in &output.diagnostics {
+    match diagnostic.severity {
+      Severity::Warning => warn!("{diagnostic}"),
+      Severity::Error => error!("{diagnostic}"),
+    }
+  }
+
+  let errors = output.errors().count();
+  if errors > 0 {
+    Err(anyhow!("failed to parse: {errors} errors"))?
+  }
 
-  Ok(story)
+  Ok(output.story)
 }
@@ -1,35 +1,485 @@
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
-use nahida_core::easing::EasingFunction;
+use nahida_core::{
+  easing::{EasingFunction, StepPosition},
+  story::{AnimationDirection, AnimationFill, AnimationIteration, AnimationType, TransitionType},
+  Color,
+};
+
+use super::{keyword, Keywords};
+
+pub const INFINITE: &str = "infinite";
+
+pub const DIRECTIONS: Keywords<AnimationDirection> = &[
+  ("normal", AnimationDirection::Normal),
+  ("reverse", AnimationDirection::Reverse),
+  ("alternate", AnimationDirection::Alternate),
+  ("alternate-reverse", AnimationDirection::AlternateReverse),
+];
+
+pub const FILLS: Keywords<AnimationFill> = &[
+  ("none", AnimationFill::None),
+  ("forwards", AnimationFill::Forwards),
+  ("backwards", AnimationFill::Backwards),
+  ("both", AnimationFill::Both),
+];
+
+/// `<step-position>` in `steps()`
+pub const STEP_POSITIONS: Keywords<StepPosition> = &[
+  ("jump-start", StepPosition::JumpStart),
+  ("jump-end", StepPosition::JumpEnd),
+  ("jump-none", StepPosition::JumpNone),
+  ("jump-both", StepPosition::JumpBoth),
+  ("start", StepPosition::JumpStart),
+  ("end", StepPosition::JumpEnd),
+];
+
+/// easings written as a single word
+pub const EASINGS: Keywords<fn() -> EasingFunction> = &[
+  ("linear", EasingFunction::linear),
+  ("ease", EasingFunction::ease),
+  ("ease-in", EasingFunction::ease_in),
+  ("ease-out", EasingFunction::ease_out),
+  ("ease-in-out", EasingFunction::ease_in_out),
+  ("step-start", EasingFunction::step_start),
+  ("step-end", EasingFunction::step_end),
+  ("spring", || EasingFunction::spring(1.0, 100.0, 10.0)),
+  ("bounce", || EasingFunction::bounce(3)),
+  ("elastic", || EasingFunction::elastic(1.0, 0.3)),
+  ("back", || EasingFunction::back(1.70158)),
+];
+
+type ParseEasing = fn(&[&str]) -> Option<EasingFunction>;
+
+/// easings written as `name(args)`
+pub const EASING_FUNCTIONS: Keywords<ParseEasing> = &[
+  ("cubic-bezier", parse_cubic_bezier),
+  ("steps", parse_steps),
+  ("linear", parse_linear_stops),
+  ("spring", parse_spring),
+  ("bounce", parse_bounce),
+  ("elastic", parse_elastic),
+  ("back", parse_back),
+];
+
+/// animations written as `name(arg)`
+pub const EFFECTS: Keywords<fn(&str) -> Option<AnimationType>> = &[
+  ("rotate", |arg| {
+    parse_angle(arg).map(|degrees| AnimationType::Rotate { degrees })
+  }),
+  ("scale", |arg| {
+    parse_ratio(arg)
+      .filter(|x| *x >= 0.0)
+      .map(|factor| AnimationType::Scale { factor })
+  }),
+  ("opacity", |arg| {
+    parse_ratio(arg)
+      .filter(|x| (0.0..=1.0).contains(x))
+      .map(|opacity| AnimationType::Opacity { opacity })
+  }),
+  ("blur", |arg| {
+    parse_length(arg).map(|radius| AnimationType::Blur { radius })
+  }),
+  ("tint", |arg| {
+    Color::hex(arg).map(|color| AnimationType::Tint { color })
+  }),
+];
+
+pub const RULE: &str = "rule";
 
 pub fn parse_time(time: &str) -> Option<Duration> {
   match () {
     _ if time.ends_with("ms") => time[..time.len() - 2]
       .parse()
       .ok()
-      .and_then(|x| Some(Duration::from_millis(x))),
+      .map(Duration::from_millis),
 
-    _ if time.ends_with("s") => time[..time.len() - 1]
-      .parse()
+    _ if time.ends_with('s') => time[..time.len() - 1].parse().ok().map(Duration::from_secs),
+
+    _ => None,
+  }
+}
+
+/// parse `infinite` or a non-negative number
+pub fn parse_iteration(word: &str) -> Option<AnimationIteration> {
+  match word {
+    INFINITE => Some(AnimationIteration::Infinite),
+    word => word
+      .parse::<f32>()
       .ok()
-      .and_then(|x| Some(Duration::from_secs(x))),
+      .filter(|x| x.is_finite() && *x >= 0.0)
+      .map(AnimationIteration::Count),
+  }
+}
 
+pub fn parse_direction(word: &str) -> Option<AnimationDirection> {
+  keyword(DIRECTIONS, word)
+}
+
+pub fn parse_fill(word: &str) -> Option<AnimationFill> {
+  keyword(FILLS, word)
+}
+
+/// parse `<number>` or `<percentage>`
+fn parse_ratio(param: &str) -> Option<f32> {
+  parse_percent(param)
+    .or_else(|| param.parse().ok())
+    .filter(|x: &f32| x.is_finite())
+}
+
+/// parse `<angle>` into degrees
+fn parse_angle(param: &str) -> Option<f32> {
+  let (value, unit) = match () {
+    _ if param.ends_with("deg") => (&param[..param.len() - 3], 1.0),
+    _ if param.ends_with("rad") => (&param[..param.len() - 3], 180.0 / std::f32::consts::PI),
+    _ if param.ends_with("turn") => (&param[..param.len() - 4], 360.0),
+    _ => (param, 1.0),
+  };
+
+  value
+    .parse::<f32>()
+    .ok()
+    .filter(|x| x.is_finite())
+    .map(|x| x * unit)
+}
+
+/// parse `<length>` in pixels
+fn parse_length(param: &str) -> Option<f32> {
+  param
+    .strip_suffix("px")
+    .unwrap_or(param)
+    .parse::<f32>()
+    .ok()
+    .filter(|x| x.is_finite() && *x >= 0.0)
+}
+
+/// parse `rotate(<angle>)`, `scale(<number>)`, `opacity(<number>)`,
+/// `blur(<length>)` and `tint(<color>)`
+pub fn parse_effect(word: &str) -> Option<AnimationType> {
+  let (name, args) = parse_function(word)?;
+  let [arg] = args[..] else {
+    return None;
+  };
+
+  keyword(EFFECTS, name)?(arg)
+}
+
+/// parse `rule(<url> [, <softness>])`
+pub fn parse_rule(word: &str) -> Option<TransitionType> {
+  let (name, args) = parse_function(word)?;
+  if name != RULE {
+    return None;
+  }
+
+  let (mask, softness) = match args[..] {
+    [mask] => (mask, 0.1),
+    [mask, softness] => (mask, parse_ratio(softness)?),
+    _ => return None,
+  };
+
+  let valid = !mask.is_empty() && (0.0..=1.0).contains(&softness);
+  valid.then(|| TransitionType::Rule {
+    mask: PathBuf::from(mask),
+    softness,
+  })
+}
+
+/// split `name(a, b, c)` into its name and arguments
+pub fn parse_function(word: &str) -> Option<(&str, Vec<&str>)> {
+  let (name, args) = word.strip_suffix(')')?.split_once('(')?;
+  let args = args.split(',').map(|x| x.trim()).collect();
+
+  Some((name, args))
+}
+
+fn parse_percent(param: &str) -> Option<f32> {
+  param
+    .strip_suffix('%')
+    .and_then(|x| x.parse::<f32>().ok())
+    .map(|x| x / 100.0)
+}
+
+/// parse `cubic-bezier(<x1>, <y1>, <x2>, <y2>)`
+fn parse_cubic_bezier(args: &[&str]) -> Option<EasingFunction> {
+  let args = args
+    .iter()
+    .map(|x| x.parse::<f32>().ok())
+    .collect::<Option<Vec<_>>>()?;
+
+  match args[..] {
+    [x1, y1, x2, y2] if (0.0..=1.0).contains(&x1) && (0.0..=1.0).contains(&x2) => {
+      Some(EasingFunction::cubic_bezier(x1, y1, x2, y2))
+    }
     _ => None,
   }
 }
 
-pub fn parse_easing(easing: &str) -> Option<EasingFunction> {
-  match easing {
-    "linear" => Some(EasingFunction::linear()),
+/// parse `steps(<integer> [, <step-position>])`
+fn parse_steps(args: &[&str]) -> Option<EasingFunction> {
+  let count = args.first()?.parse::<u32>().ok()?;
+  let position = match args.get(1..)? {
+    [] => StepPosition::JumpEnd,
+    [word] => keyword(STEP_POSITIONS, word)?,
+    _ => None?,
+  };
 
-    "ease" => Some(EasingFunction::ease()),
-    "ease-in" => Some(EasingFunction::ease_in()),
-    "ease-out" => Some(EasingFunction::ease_out()),
-    "ease-in-out" => Some(EasingFunction::ease_in_out()),
+  match (count, position) {
+    (0, _) | (1, StepPosition::JumpNone) => None,
+    _ => Some(EasingFunction::steps(count, position)),
+  }
+}
+
+/// parse `linear( [ <number> && <percentage>{0,2} ]# )`
+fn parse_linear_stops(args: &[&str]) -> Option<EasingFunction> {
+  let mut stops: Vec<(Option<f32>, f32)> = Vec::new();
+
+  for arg in args {
+    let mut words = arg.split_whitespace();
+    let output = words.next()?.parse::<f32>().ok()?;
+    let inputs = words.map(parse_percent).collect::<Option<Vec<_>>>()?;
+
+    match inputs[..] {
+      [] => stops.push((None, output)),
+      [input] => stops.push((Some(input), output)),
+      [from, to] => {
+        stops.push((Some(from), output));
+        stops.push((Some(to), output));
+      }
+      _ => None?,
+    }
+  }
+
+  if stops.len() < 2 {
+    return None;
+  }
+
+  // the first and last stops default to 0% and 100%
+  let last = stops.len() - 1;
+  stops[0].0.get_or_insert(0.0);
+  stops[last].0.get_or_insert(1.0);
+
+  // inputs never go backwards
+  let mut largest = f32::MIN;
+  for (input, _) in stops.iter_mut() {
+    if let Some(input) = input {
+      *input = input.max(largest);
+      largest = *input;
+    }
+  }
+
+  // spread stops without input evenly between their neighbours
+  let mut points = Vec::with_capacity(stops.len());
+  let mut index = 0;
+  while index < stops.len() {
+    match stops[index] {
+      (Some(input), output) => {
+        points.push((input, output));
+        index += 1;
+      }
+      (None, _) => {
+        let end = index + stops[index..].iter().position(|(x, _)| x.is_some())?;
+        let from = points.last().map_or(0.0, |(x, _)| *x);
+        let to = stops[end].0?;
+        let count = (end - index + 1) as f32;
+
+        for (i, (_, output)) in stops[index..end].iter().enumerate() {
+          points.push((from + (to - from) * (i + 1) as f32 / count, *output));
+        }
+        index = end;
+      }
+    }
+  }
+
+  Some(EasingFunction::linear_stops(points))
+}
+
+fn parse_numbers<const N: usize>(args: &[&str]) -> Option<[f32; N]> {
+  let args = args
+    .iter()
+    .map(|x| x.parse::<f32>().ok().filter(|x| x.is_finite()))
+    .collect::<Option<Vec<_>>>()?;
+
+  args.try_into().ok()
+}
+
+/// parse `spring(<mass>, <stiffness>, <damping>)`
+fn parse_spring(args: &[&str]) -> Option<EasingFunction> {
+  match parse_numbers(args)? {
+    [mass, stiffness, damping] if mass > 0.0 && stiffness > 0.0 && damping > 0.0 => {
+      Some(EasingFunction::spring(mass, stiffness, damping))
+    }
+    _ => None,
+  }
+}
 
-    "step-start" => Some(EasingFunction::step_start()),
-    "step-end" => Some(EasingFunction::step_end()),
+/// parse `bounce(<integer>)`
+fn parse_bounce(args: &[&str]) -> Option<EasingFunction> {
+  match args {
+    [bounces] => bounces.parse().ok().map(EasingFunction::bounce),
+    _ => None,
+  }
+}
+
+/// parse `elastic(<amplitude> [, <period>])`
+fn parse_elastic(args: &[&str]) -> Option<EasingFunction> {
+  let (amplitude, period) = match args.len() {
+    1 => parse_numbers(args).map(|[amplitude]| (amplitude, 0.3))?,
+    _ => parse_numbers(args).map(|[amplitude, period]| (amplitude, period))?,
+  };
+
+  (amplitude >= 1.0 && period > 0.0).then(|| EasingFunction::elastic(amplitude, period))
+}
 
+/// parse `back(<overshoot>)`
+fn parse_back(args: &[&str]) -> Option<EasingFunction> {
+  match parse_numbers(args)? {
+    [overshoot] if overshoot >= 0.0 => Some(EasingFunction::back(overshoot)),
     _ => None,
   }
 }
+
+pub fn parse_easing(easing: &str) -> Option<EasingFunction> {
+  match parse_function(easing) {
+    Some((name, args)) => keyword(EASING_FUNCTIONS, name)?(&args),
+    None => keyword(EASINGS, easing).map(|easing| easing()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nahida_core::{
+    easing::{EasingFunction, StepPosition},
+    story::{AnimationType, TransitionType},
+    Color,
+  };
+
+  use super::{parse_easing, parse_effect, parse_rule};
+
+  #[test]
+  fn test_parse_easing() {
+    assert_eq!(parse_easing("ease-in"), Some(EasingFunction::ease_in()));
+    assert_eq!(
+      parse_easing("cubic-bezier(0.1, 0.7, 1.0, 0.1)"),
+      Some(EasingFunction::cubic_bezier(0.1, 0.7, 1.0, 0.1))
+    );
+    assert_eq!(parse_easing("cubic-bezier(1.1, 0.7, 1.0, 0.1)"), None);
+    assert_eq!(parse_easing("cubic-bezier(0.1, 0.7)"), None);
+
+    assert_eq!(
+      parse_easing("steps(4)"),
+      Some(EasingFunction::steps(4, StepPosition::JumpEnd))
+    );
+    assert_eq!(
+      parse_easing("steps(4, jump-both)"),
+      Some(EasingFunction::steps(4, StepPosition::JumpBoth))
+    );
+    assert_eq!(parse_easing("steps(1, jump-none)"), None);
+    assert_eq!(parse_easing("steps(0)"), None);
+
+    assert_eq!(
+      parse_easing("linear(0, 0.25 75%, 1)"),
+      Some(EasingFunction::linear_stops(vec![
+        (0.0, 0.0),
+        (0.75, 0.25),
+        (1.0, 1.0)
+      ]))
+    );
+    assert_eq!(
+      parse_easing("linear(0, 0.5, 0.8, 1 50%, 1)"),
+      Some(EasingFunction::linear_stops(vec![
+        (0.0, 0.0),
+        (0.5 / 3.0, 0.5),
+        (1.0 / 3.0, 0.8),
+        (0.5, 1.0),
+        (1.0, 1.0)
+      ]))
+    );
+    assert_eq!(
+      parse_easing("linear(0 20% 40%, 1 30%)"),
+      Some(EasingFunction::linear_stops(vec![
+        (0.2, 0.0),
+        (0.4, 0.0),
+        (0.4, 1.0)
+      ]))
+    );
+    assert_eq!(parse_easing("linear(0)"), None);
+
+    assert_eq!(
+      parse_easing("spring"),
+      Some(EasingFunction::spring(1.0, 100.0, 10.0))
+    );
+    assert_eq!(
+      parse_easing("spring(2, 200, 15)"),
+      Some(EasingFunction::spring(2.0, 200.0, 15.0))
+    );
+    assert_eq!(parse_easing("spring(1, 100, 0)"), None);
+    assert_eq!(parse_easing("bounce(5)"), Some(EasingFunction::bounce(5)));
+    assert_eq!(parse_easing("bounce(1.5)"), None);
+    assert_eq!(
+      parse_easing("elastic(1.5)"),
+      Some(EasingFunction::elastic(1.5, 0.3))
+    );
+    assert_eq!(
+      parse_easing("elastic(1, 0.5)"),
+      Some(EasingFunction::elastic(1.0, 0.5))
+    );
+    assert_eq!(parse_easing("elastic(0.5)"), None);
+    assert_eq!(parse_easing("back(2.5)"), Some(EasingFunction::back(2.5)));
+    assert_eq!(parse_easing("back(-1)"), None);
+  }
+
+  #[test]
+  fn test_parse_effect() {
+    assert_eq!(
+      parse_effect("rotate(15deg)"),
+      Some(AnimationType::Rotate { degrees: 15.0 })
+    );
+    assert_eq!(
+      parse_effect("rotate(-0.5turn)"),
+      Some(AnimationType::Rotate { degrees: -180.0 })
+    );
+    assert_eq!(
+      parse_effect("scale(120%)"),
+      Some(AnimationType::Scale { factor: 1.2 })
+    );
+    assert_eq!(parse_effect("scale(-1)"), None);
+    assert_eq!(
+      parse_effect("opacity(0.5)"),
+      Some(AnimationType::Opacity { opacity: 0.5 })
+    );
+    assert_eq!(parse_effect("opacity(2)"), None);
+    assert_eq!(
+      parse_effect("blur(4px)"),
+      Some(AnimationType::Blur { radius: 4.0 })
+    );
+    assert_eq!(
+      parse_effect("tint(#f80)"),
+      Some(AnimationType::Tint {
+        color: Color::hex("#ff8800").unwrap()
+      })
+    );
+    assert_eq!(parse_effect("tint(orange)"), None);
+    assert_eq!(parse_effect("rotate(1deg, 2deg)"), None);
+  }
+
+  #[test]
+  fn test_parse_rule() {
+    assert_eq!(
+      parse_rule("rule(./rules/swirl.png)"),
+      Some(TransitionType::Rule {
+        mask: "./rules/swirl.png".into(),
+        softness: 0.1
+      })
+    );
+    assert_eq!(
+      parse_rule("rule(./rules/swirl.png, 25%)"),
+      Some(TransitionType::Rule {
+        mask: "./rules/swirl.png".into(),
+        softness: 0.25
+      })
+    );
+    assert_eq!(parse_rule("rule(./rules/swirl.png, 2)"), None);
+    assert_eq!(parse_rule("rule()"), None);
+    assert_eq!(parse_rule("mask(./rules/swirl.png)"), None);
+  }
+}
@@ -7,3 +7,51 @@ mod size;
 mod token;
 
 pub use token::Tokenizer;
+
+use animation::{
+  DIRECTIONS, EASINGS, EASING_FUNCTIONS, EFFECTS, FILLS, INFINITE, RULE, STEP_POSITIONS,
+};
+use position::POSITIONS;
+use size::SIZES;
+use token::{ANIMATIONS, REMOVE, TRANSITIONS};
+
+/// words with what they stand for
+type Keywords<T> = &'static [(&'static str, T)];
+
+/// the value of the word in the keyword table
+fn keyword<T: Clone>(table: &[(&str, T)], word: &str) -> Option<T> {
+  table
+    .iter()
+    .find(|(keyword, _)| *keyword == word)
+    .map(|(_, value)| value.clone())
+}
+
+fn words<T>(table: Keywords<T>) -> impl Iterator<Item = &'static str> {
+  table.iter().map(|(word, _)| *word)
+}
+
+/// every word the tokenizer takes as a keyword
+pub fn keywords() -> Vec<&'static str> {
+  words(TRANSITIONS)
+    .chain(ANIMATIONS.iter().copied())
+    .chain([REMOVE, INFINITE])
+    .chain(words(EASINGS))
+    .chain(words(DIRECTIONS))
+    .chain(words(FILLS))
+    .chain(words(POSITIONS))
+    .chain(words(SIZES))
+    .collect()
+}
+
+/// names of the functions the tokenizer takes, like `rotate(15deg)`
+pub fn functions() -> Vec<&'static str> {
+  words(EASING_FUNCTIONS)
+    .chain(words(EFFECTS))
+    .chain([RULE])
+    .collect()
+}
+
+/// `<step-position>` keywords of `steps()`
+pub fn step_positions() -> Vec<&'static str> {
+  words(STEP_POSITIONS).collect()
+}
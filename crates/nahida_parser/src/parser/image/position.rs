@@ -1,5 +1,7 @@
 use nahida_core::location::Position;
 
+use super::{keyword, Keywords};
+
 #[derive(Debug, Clone, Copy)]
 pub enum PositionKeyword {
   Left,
@@ -10,13 +12,16 @@ pub enum PositionKeyword {
   Percent(f32),
 }
 
+pub const POSITIONS: Keywords<PositionKeyword> = &[
+  ("left", PositionKeyword::Left),
+  ("right", PositionKeyword::Right),
+  ("top", PositionKeyword::Top),
+  ("bottom", PositionKeyword::Bottom),
+  ("center", PositionKeyword::Center),
+];
+
 pub fn parse_position_keyword(param: &str) -> Option<PositionKeyword> {
   match param {
-    "left" => Some(PositionKeyword::Left),
-    "right" => Some(PositionKeyword::Right),
-    "top" => Some(PositionKeyword::Top),
-    "bottom" => Some(PositionKeyword::Bottom),
-    "center" => Some(PositionKeyword::Center),
     p if p.ends_with('%') => {
       if let Ok(percent) = p.trim_end_matches('%').parse::<f32>() {
         Some(PositionKeyword::Percent(percent / 100.0))
@@ -24,7 +29,7 @@ pub fn parse_position_keyword(param: &str) -> Option<PositionKeyword> {
         None
       }
     }
-    _ => None,
+    _ => keyword(POSITIONS, param),
   }
 }
 
@@ -1,5 +1,7 @@
 use nahida_core::location::Size;
 
+use super::{keyword, Keywords};
+
 #[derive(Debug, Clone, Copy)]
 pub enum SizeKeyword {
   Contain,
@@ -9,12 +11,15 @@ pub enum SizeKeyword {
   Percent(f32),
 }
 
+pub const SIZES: Keywords<SizeKeyword> = &[
+  ("contain", SizeKeyword::Contain),
+  ("cover", SizeKeyword::Cover),
+  ("fill", SizeKeyword::Fill),
+  ("auto", SizeKeyword::Auto),
+];
+
 pub fn parse_size_keyword(param: &str) -> Option<SizeKeyword> {
   match param {
-    "contain" => Some(SizeKeyword::Contain),
-    "cover" => Some(SizeKeyword::Cover),
-    "fill" => Some(SizeKeyword::Fill),
-    "auto" => Some(SizeKeyword::Auto),
     p if p.ends_with('%') => {
       if let Ok(percent) = p.trim_end_matches('%').parse::<f32>() {
         Some(SizeKeyword::Percent(percent / 100.0))
@@ -22,7 +27,7 @@ pub fn parse_size_keyword(param: &str) -> Option<SizeKeyword> {
         None
       }
     }
-    _ => None,
+    _ => keyword(SIZES, param),
   }
 }
 
@@ -1,43 +1,158 @@
-use std::{iter::Peekable, time::Duration};
+use std::{iter::Peekable, ops::Range, time::Duration};
 
-use log::warn;
 use nahida_core::{
   easing::EasingFunction,
   location::Location,
-  story::{Animation, AnimationType, Transition, TransitionType},
+  story::{
+    Animation, AnimationDirection, AnimationFill, AnimationIteration, AnimationType, Keyframe,
+    Transition, TransitionType,
+  },
 };
 
+use crate::parser::ParseErrorType;
+
 use super::{
-  animation::{parse_easing, parse_time},
+  animation::{
+    parse_direction, parse_easing, parse_effect, parse_fill, parse_function, parse_iteration,
+    parse_rule, parse_time,
+  },
+  keyword,
   position::{parse_position, parse_position_keyword},
   size::{parse_size, parse_size_keyword},
+  Keywords,
 };
 
+const TO: &str = "to";
+const WAIT: &str = "wait";
+const SHAKE: &str = "shake";
+const THEN: &str = "then";
+pub const REMOVE: &str = "remove";
+
+/// animations besides the effects, `then` joins the keyframes
+pub const ANIMATIONS: &[&str] = &[TO, WAIT, SHAKE, THEN];
+
+/// transitions besides `rule()`, `shake` goes along with any of them
+pub const TRANSITIONS: Keywords<TransitionType> = &[
+  ("fade-in", TransitionType::FadeIn),
+  ("fade-out", TransitionType::FadeOut),
+  ("conic-in", TransitionType::ConicIn),
+  ("conic-out", TransitionType::ConicOut),
+  ("blinds-in", TransitionType::BlindsIn),
+  ("blinds-out", TransitionType::BlindsOut),
+  (SHAKE, TransitionType::Shake),
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+  text: &'a str,
+  /// byte offset in the tokenized text
+  start: usize,
+}
+
+impl<'a> Token<'a> {
+  fn range(&self) -> Range<usize> {
+    self.start..self.start + self.text.len()
+  }
+}
+
+/// byte range covering all the tokens
+fn span(tokens: &[Token]) -> Range<usize> {
+  match (tokens.first(), tokens.last()) {
+    (Some(first), Some(last)) => first.start..last.range().end,
+    _ => 0..0,
+  }
+}
+
+fn parse_transition_type(word: &str) -> Option<TransitionType> {
+  keyword(TRANSITIONS, word).or_else(|| parse_rule(word))
+}
+
+fn unknown_token(word: &str) -> ParseErrorType {
+  match parse_function(word) {
+    Some(_) => ParseErrorType::InvalidFunction(word.to_string()),
+    None => ParseErrorType::UnknownToken(word.to_string()),
+  }
+}
+
+/// end the current segment, leaving time and easing to the next one
+fn take_keyframe(
+  location: Option<Location>,
+  time: &mut Option<Duration>,
+  easing: &mut EasingFunction,
+) -> Keyframe {
+  Keyframe {
+    location,
+    time: time.take().unwrap_or(Duration::from_secs(1)),
+    easing: std::mem::replace(easing, EasingFunction::Linear),
+  }
+}
+
 pub struct Tokenizer<'a> {
-  token: Peekable<std::vec::IntoIter<&'a str>>,
+  token: Peekable<std::vec::IntoIter<Token<'a>>>,
+  issues: Vec<(ParseErrorType, Range<usize>)>,
 }
 
 impl<'a> Tokenizer<'a> {
+  /// split by whitespaces and `/`, keeping `name(a, b)` as a single token
   pub fn new(title: &'a str) -> Self {
-    let vec = title
-      .split_whitespace()
-      .flat_map(|x| {
-        if let Some(index) = x.find('/') {
-          vec![&x[..index], &x[index..index + 1], &x[index + 1..]]
-        } else {
-          vec![x]
+    let mut vec = Vec::new();
+    let mut start = None;
+    let mut depth = 0usize;
+
+    let mut flush = |start: &mut Option<usize>, end: usize| {
+      if let Some(start) = start.take() {
+        vec.push(Token {
+          text: &title[start..end],
+          start,
+        });
+      }
+    };
+
+    for (index, c) in title.char_indices() {
+      match c {
+        '(' => depth += 1,
+        ')' => depth = depth.saturating_sub(1),
+        '/' if depth == 0 => {
+          flush(&mut start, index);
+          start = Some(index);
+          flush(&mut start, index + 1);
+          continue;
+        }
+        c if c.is_whitespace() && depth == 0 => {
+          flush(&mut start, index);
+          continue;
         }
-      })
-      .filter(|x| x.len() > 0)
-      .collect::<Vec<_>>();
+        _ => {}
+      }
+      start.get_or_insert(index);
+    }
+    flush(&mut start, title.len());
 
     Self {
       token: vec.into_iter().peekable(),
+      issues: Vec::new(),
     }
   }
 
   pub fn next(&mut self) -> Option<&'a str> {
-    self.token.next()
+    self.token.next().map(|x| x.text)
+  }
+
+  /// report all the tokens left as unknown
+  pub fn finish(&mut self) {
+    while let Some(token) = self.token.next() {
+      let ty = ParseErrorType::UnknownToken(token.text.to_string());
+      self.report(ty, token.range());
+    }
+  }
+
+  /// issues found so far with their byte ranges, left for the parser to report
+  pub fn take_issues(&mut self) -> Vec<(ParseErrorType, Range<usize>)> {
+    std::mem::take(&mut self.issues)
+  }
+
+  fn report(&mut self, ty: ParseErrorType, range: Range<usize>) {
+    self.issues.push((ty, range));
   }
 }
 
@@ -45,104 +160,405 @@ impl<'a> Tokenizer<'a> {
   /// parse `[ <position> ] [ / <size> ]`
   pub fn parse_location(&mut self) -> Location {
     let mut position_words = Vec::new();
+    let mut position_tokens = Vec::new();
     let mut size_words = Vec::new();
+    let mut size_tokens = Vec::new();
 
     // [ <position> ]
-    while let Some(x) = self.token.peek() {
-      match parse_position_keyword(x) {
+    while let Some(&x) = self.token.peek() {
+      match parse_position_keyword(x.text) {
         Some(word) => position_words.push(word),
         None => break,
       }
-      self.token.next();
+      position_tokens.extend(self.token.next());
     }
 
     // [ / <size> ]
-    if let Some(&"/") = self.token.peek() {
-      self.token.next();
-
-      while let Some(x) = self.token.peek() {
-        match parse_size_keyword(x) {
+    if let Some(slash) = self.token.next_if(|x| x.text == "/") {
+      while let Some(&x) = self.token.peek() {
+        match parse_size_keyword(x.text) {
           Some(word) => size_words.push(word),
           None => break,
         }
-        self.token.next();
+        size_tokens.extend(self.token.next());
       }
 
       // if have / but no size words
-      if size_words.len() == 0 {
-        warn!("possible missing size words");
+      if size_words.is_empty() {
+        self.report(ParseErrorType::MissingSizeWords, slash.range());
       }
     }
 
-    let position = parse_position(&position_words).unwrap_or_default();
-    let size = parse_size(&size_words).unwrap_or_default();
-    let location = Location { position, size };
-
-    location
+    let position = parse_position(&position_words).unwrap_or_else(|| {
+      let words = position_tokens.iter().map(|x| x.text).collect::<Vec<_>>();
+      self.report(
+        ParseErrorType::InvalidPosition(words.join(" ")),
+        span(&position_tokens),
+      );
+      Default::default()
+    });
+    let size = parse_size(&size_words).unwrap_or_else(|| {
+      let words = size_tokens.iter().map(|x| x.text).collect::<Vec<_>>();
+      self.report(
+        ParseErrorType::InvalidSize(words.join(" ")),
+        span(&size_tokens),
+      );
+      Default::default()
+    });
+    Location { position, size }
   }
 
+  /// parse `<animation> [ then <animation> ]*`, where every segment after `then`
+  /// is a keyframe with its own time and easing
   pub fn parse_animation(&mut self) -> Option<Animation> {
-    let mut ty = None;
-    let mut time = Duration::from_secs(60);
+    let mut keyword: Option<&str> = None;
+    // `to` or `wait` of the current segment
+    let mut segment: Option<Option<Location>> = None;
+    // any other kind of animation
+    let mut effect = None;
+    let mut keyframes = Vec::new();
+    let mut then = None;
+
+    let mut time = None;
+    let mut delay = None;
     let mut easing = EasingFunction::Linear;
+    let mut iteration = AnimationIteration::default();
+    let mut direction = AnimationDirection::default();
+    let mut fill = AnimationFill::default();
 
-    while let Some(param) = self.token.next() {
-      match param {
-        "to" => {
-          ty = Some(AnimationType::To {
-            location: self.parse_location(),
-          })
+    while let Some(token) = self.token.next() {
+      let found = match token.text {
+        TO => {
+          segment = Some(Some(self.parse_location()));
+          true
+        }
+        WAIT => {
+          segment = Some(None);
+          true
+        }
+        SHAKE => {
+          effect = Some(AnimationType::Shake);
+          true
+        }
+        THEN => {
+          match (segment.take(), keyword) {
+            (Some(location), Some(TO | WAIT)) => {
+              keyframes.push(take_keyframe(location, &mut time, &mut easing))
+            }
+            _ => self.report(ParseErrorType::InvalidKeyframe, token.range()),
+          }
+          keyword = None;
+          then = Some(token);
+          false
+        }
+        word if parse_effect(word).is_some() => {
+          effect = parse_effect(word);
+          true
         }
-        "shake" => ty = Some(AnimationType::Shake),
         word => {
           if let Some(t) = parse_time(word) {
-            time = t;
+            // the first time is the duration and the second one is the delay
+            match (time, delay) {
+              (None, _) => time = Some(t),
+              (Some(_), None) => delay = Some(t),
+              _ => self.report(unknown_token(word), token.range()),
+            }
           } else if let Some(e) = parse_easing(word) {
             easing = e;
+          } else if let Some(i) = parse_iteration(word) {
+            iteration = i;
+          } else if let Some(d) = parse_direction(word) {
+            direction = d;
+          } else if let Some(f) = parse_fill(word) {
+            fill = f;
+          } else {
+            self.report(unknown_token(word), token.range());
           }
+          false
+        }
+      };
+
+      if found {
+        if let Some(keyword) = keyword {
+          self.report(
+            ParseErrorType::ConflictingAnimation(keyword.to_string(), token.text.to_string()),
+            token.range(),
+          );
         }
+        keyword = Some(token.text);
       }
     }
 
-    ty.and_then(|ty| Some(Animation { ty, time, easing }))
+    let ty = match then {
+      None => match keyword {
+        Some(TO) => AnimationType::To {
+          location: segment.flatten()?,
+        },
+        Some(WAIT) | None => return None,
+        Some(_) => effect?,
+      },
+      Some(then) => {
+        match (segment, keyword) {
+          (Some(location), Some(TO | WAIT)) => {
+            keyframes.push(take_keyframe(location, &mut time, &mut easing))
+          }
+          _ => self.report(ParseErrorType::InvalidKeyframe, then.range()),
+        }
+        AnimationType::Keyframes(keyframes)
+      }
+    };
+
+    // keyframes carry their own time and easing
+    let time = match &ty {
+      AnimationType::Keyframes(keyframes) => keyframes.iter().map(|x| x.time).sum(),
+      _ => time.unwrap_or(Duration::from_secs(60)),
+    };
+
+    Some(Animation {
+      ty,
+      time,
+      easing,
+      delay: delay.unwrap_or_default(),
+      iteration,
+      direction,
+      fill,
+    })
   }
 
   pub fn parse_transition(&mut self) -> Option<Transition> {
     let mut ty = None;
+    let mut keyword: Option<&str> = None;
     let mut time = Duration::from_secs(1);
     let mut easing = EasingFunction::linear();
+    let mut shake = false;
 
     while let Some(token) = self.token.next() {
-      match token {
-        "fade-in" => ty = Some(TransitionType::FadeIn),
-        "fade-out" => ty = Some(TransitionType::FadeOut),
-        "conic-in" => ty = Some(TransitionType::ConicIn),
-        "conic-out" => ty = Some(TransitionType::ConicOut),
-        "blinds-in" => ty = Some(TransitionType::BlindsIn),
-        "blinds-out" => ty = Some(TransitionType::BlindsOut),
-        "shake" => ty = Some(TransitionType::Shake),
+      let transition = match token.text {
+        SHAKE => {
+          // shake goes along with any other transition
+          shake = true;
+          None
+        }
+        word if parse_transition_type(word).is_some() => parse_transition_type(word),
         word => {
           if let Some(t) = parse_time(word) {
             time = t;
           } else if let Some(e) = parse_easing(word) {
             easing = e;
+          } else {
+            self.report(unknown_token(word), token.range());
           }
+          None
+        }
+      };
+
+      if let Some(transition) = transition {
+        if let Some(keyword) = keyword {
+          self.report(
+            ParseErrorType::ConflictingTransition(keyword.to_string(), token.text.to_string()),
+            token.range(),
+          );
         }
+        keyword = Some(token.text);
+        ty = Some(transition);
       }
     }
 
-    ty.and_then(|ty| Some(Transition { ty, time, easing }))
+    let ty = ty.or(shake.then_some(TransitionType::Shake))?;
+    Some(Transition {
+      ty,
+      time,
+      easing,
+      shake,
+    })
   }
 
   pub fn parse_name(&mut self) -> Option<String> {
-    self.token.next().and_then(|x| Some(x.to_string()))
+    self.next().map(|x| x.to_string())
   }
 
   pub fn parse_remove(&mut self) -> bool {
-    let remove = matches!(self.token.peek(), Some(&"remove"));
-    if remove {
-      self.next();
+    self.token.next_if(|x| x.text == REMOVE).is_some()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use nahida_core::{
+    easing::EasingFunction,
+    story::{
+      AnimationDirection, AnimationFill, AnimationIteration, AnimationType, Keyframe,
+      TransitionType,
+    },
+    Location, Position, Size,
+  };
+
+  use crate::parser::ParseErrorType;
+
+  use super::Tokenizer;
+
+  #[test]
+  fn test_tokenize() {
+    let mut title =
+      Tokenizer::new("left/cover  cubic-bezier(0.1, 0.7,\n1.0, 0.1) linear(0, 1 50%)");
+    let mut tokens = Vec::new();
+    while let Some(token) = title.next() {
+      tokens.push(token);
     }
-    remove
+
+    assert_eq!(
+      tokens,
+      vec![
+        "left",
+        "/",
+        "cover",
+        "cubic-bezier(0.1, 0.7,\n1.0, 0.1)",
+        "linear(0, 1 50%)"
+      ]
+    );
+
+    let mut alt = Tokenizer::new("fade-in 2s cubic-bezier(0.1, 0.7, 1.0, 0.1)");
+    let transition = alt.parse_transition().unwrap();
+
+    assert_eq!(
+      transition.easing,
+      EasingFunction::cubic_bezier(0.1, 0.7, 1.0, 0.1)
+    );
+
+    // shake goes along with the other transition in either order
+    for text in ["fade-in shake 2s", "shake fade-in 2s"] {
+      let transition = Tokenizer::new(text).parse_transition().unwrap();
+      assert_eq!(transition.ty, TransitionType::FadeIn);
+      assert_eq!(transition.time, Duration::from_secs(2));
+      assert!(transition.shake);
+    }
+
+    let transition = Tokenizer::new("shake").parse_transition().unwrap();
+    assert_eq!(transition.ty, TransitionType::Shake);
+    assert!(transition.shake);
+
+    let mut title = Tokenizer::new("to left 20s 2s infinite alternate both");
+    let animation = title.parse_animation().unwrap();
+
+    assert_eq!(animation.time, Duration::from_secs(20));
+    assert_eq!(animation.delay, Duration::from_secs(2));
+    assert_eq!(animation.iteration, AnimationIteration::Infinite);
+    assert_eq!(animation.direction, AnimationDirection::Alternate);
+    assert_eq!(animation.fill, AnimationFill::Both);
+
+    let mut title =
+      Tokenizer::new("to left / 120% 3s then wait 1s then to right / cover 5s ease-out");
+    let animation = title.parse_animation().unwrap();
+
+    assert_eq!(
+      animation.ty,
+      AnimationType::Keyframes(vec![
+        Keyframe {
+          location: Some(Location {
+            position: Position(0.0, 0.5),
+            size: Size::FixedWidth(1.2),
+          }),
+          time: Duration::from_secs(3),
+          easing: EasingFunction::Linear,
+        },
+        Keyframe {
+          location: None,
+          time: Duration::from_secs(1),
+          easing: EasingFunction::Linear,
+        },
+        Keyframe {
+          location: Some(Location {
+            position: Position(1.0, 0.5),
+            size: Size::Cover,
+          }),
+          time: Duration::from_secs(5),
+          easing: EasingFunction::ease_out(),
+        },
+      ])
+    );
+    assert_eq!(animation.time, Duration::from_secs(9));
+    assert_eq!(animation.easing, EasingFunction::Linear);
+
+    let mut alt = Tokenizer::new("fade-in steps(0)");
+    alt.parse_transition();
+
+    assert_eq!(
+      alt.take_issues(),
+      vec![(ParseErrorType::InvalidFunction("steps(0)".into()), 8..16)]
+    );
+  }
+
+  #[test]
+  fn test_issues() {
+    let mut alt = Tokenizer::new("fade-inn  conic-in 2s blinds-in");
+    alt.parse_transition();
+
+    assert_eq!(
+      alt.take_issues(),
+      vec![
+        (ParseErrorType::UnknownToken("fade-inn".into()), 0..8),
+        (
+          ParseErrorType::ConflictingTransition("conic-in".into(), "blinds-in".into()),
+          22..31
+        ),
+      ]
+    );
+
+    let mut title = Tokenizer::new("left 20% 20% /");
+    title.parse_location();
+
+    assert_eq!(
+      title.take_issues(),
+      vec![
+        (ParseErrorType::MissingSizeWords, 13..14),
+        (
+          ParseErrorType::InvalidPosition("left 20% 20%".into()),
+          0..12
+        ),
+      ]
+    );
+
+    let mut title = Tokenizer::new("to left 2s 1s 3s");
+    title.parse_animation();
+
+    assert_eq!(
+      title.take_issues(),
+      vec![(ParseErrorType::UnknownToken("3s".into()), 14..16)]
+    );
+
+    let mut title = Tokenizer::new("then to left then shake");
+    title.parse_animation();
+
+    assert_eq!(
+      title.take_issues(),
+      vec![
+        (ParseErrorType::InvalidKeyframe, 0..4),
+        (ParseErrorType::InvalidKeyframe, 13..17),
+      ]
+    );
+
+    let mut title = Tokenizer::new("rotate(15deg) 2s infinite alternate");
+    let animation = title.parse_animation().unwrap();
+
+    assert_eq!(animation.ty, AnimationType::Rotate { degrees: 15.0 });
+    assert_eq!(animation.iteration, AnimationIteration::Infinite);
+    assert_eq!(title.take_issues(), vec![]);
+
+    let mut title = Tokenizer::new("left/40% 30% 20% to right shake");
+    title.parse_location();
+    title.parse_animation();
+
+    assert_eq!(
+      title.take_issues(),
+      vec![
+        (ParseErrorType::InvalidSize("40% 30% 20%".into()), 5..16),
+        (
+          ParseErrorType::ConflictingAnimation("to".into(), "shake".into()),
+          26..31
+        ),
+      ]
+    );
   }
 }
@@ -1,15 +1,44 @@
-use std::{path::PathBuf, time::Duration};
+use std::{collections::HashSet, fmt, ops::Range, path::PathBuf, time::Duration};
 
 use markdown::{
-  mdast::{Heading, Image, Link, Node, Paragraph, Root, Text},
-  unist::Position,
+  mdast::{
+    Emphasis, Heading, Image, InlineCode, Link, List, ListItem, Node, Paragraph, Root, Strong, Text,
+  },
+  unist::{Point, Position},
+};
+use nahida_core::{
+  story::{ChoiceOption, StepId, Story, StoryAction, StoryStep},
+  text::{RichText, TextSpan, TextStyle},
+  Color,
 };
-use nahida_core::story::{Story, StoryAction, StoryStep};
 use thiserror::Error;
 
 mod image;
+mod text;
 
 use image::Tokenizer;
+use text::trim_spans;
+
+pub(crate) use image::{functions, keywords, step_positions};
+
+const GOTO: &str = "goto";
+const END: &str = "end";
+const WAIT: &str = "wait";
+const ANCHOR: &str = "anchor";
+const PAUSE: &str = "pause";
+const SPEED: &str = "speed";
+const SFX: &str = "sfx";
+
+/// words a link can start with, like `[goto](./story.md)`
+pub(crate) const LINKS: &[&str] = &[GOTO, END, WAIT, ANCHOR, PAUSE, SPEED, SFX];
+
+const BG: &str = "bg";
+const FIG: &str = "fig";
+const BGM: &str = "bgm";
+const VOICE: &str = "v";
+
+/// words an image can start with, like `![bg](./background.png)`
+pub(crate) const IMAGES: &[&str] = &[BG, FIG, BGM, SFX, VOICE];
 
 #[derive(Debug, Error, PartialEq)]
 pub enum ParseErrorType {
@@ -23,10 +52,48 @@ pub enum ParseErrorType {
   InvalidLink,
   #[error("invalid image")]
   InvalidImage,
+  #[error("unknown link: {0}")]
+  UnknownLink(String),
+  #[error("unknown image: {0}")]
+  UnknownImage(String),
   #[error("figure shoud have a name")]
   NoFigureName,
   #[error("invalid wait time: {0}")]
   InvalidWaitTime(String),
+  #[error("voice is not bound to any text")]
+  OrphanVoice,
+  #[error("multiple voices in one paragraph")]
+  DuplicateVoice,
+  #[error("invalid choice option")]
+  InvalidChoice,
+  #[error("choice has {options} options but {branches} branches")]
+  ChoiceBranchMismatch { options: usize, branches: usize },
+  #[error("branches without a choice")]
+  BranchWithoutChoice,
+  #[error("possible missing size words")]
+  MissingSizeWords,
+  #[error("unknown word: {0}")]
+  UnknownToken(String),
+  #[error("conflicting transitions: {0} and {1}")]
+  ConflictingTransition(String, String),
+  #[error("conflicting animations: {0} and {1}")]
+  ConflictingAnimation(String, String),
+  #[error("invalid position: {0}")]
+  InvalidPosition(String),
+  #[error("invalid size: {0}")]
+  InvalidSize(String),
+  #[error("invalid function: {0}")]
+  InvalidFunction(String),
+  #[error("invalid keyframe")]
+  InvalidKeyframe,
+  #[error("invalid pause time: {0}")]
+  InvalidPauseTime(String),
+  #[error("invalid text speed: {0}")]
+  InvalidSpeed(String),
+  #[error("invalid anchor: {0}")]
+  InvalidAnchor(String),
+  #[error("duplicate anchor: {0}")]
+  DuplicateAnchor(String),
 }
 
 #[derive(Debug, Error, PartialEq)]
@@ -36,24 +103,130 @@ pub struct ParseError {
   position: Option<Position>,
 }
 
+impl ParseError {
+  pub fn ty(&self) -> &ParseErrorType {
+    &self.ty
+  }
+
+  /// where the error is in the source
+  pub fn position(&self) -> Option<&Position> {
+    self.position.as_ref()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  Warning,
+  Error,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Severity::Warning => f.write_str("warning"),
+      Severity::Error => f.write_str("error"),
+    }
+  }
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("{severity}: {error}")]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub error: ParseError,
+}
+
+/// best-effort story along with everything found wrong in the script
+#[derive(Debug, PartialEq)]
+pub struct ParseOutput {
+  pub story: Story,
+  pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ParseOutput {
+  /// diagnostics that make the story unusable, warnings left out
+  pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+    self
+      .diagnostics
+      .iter()
+      .filter(|diagnostic| diagnostic.severity == Severity::Error)
+  }
+
+  pub fn has_errors(&self) -> bool {
+    self.errors().next().is_some()
+  }
+}
+
 #[derive(Default)]
 pub struct NahidaParser {
+  /// report unknown words and invalid combinations in images as errors instead of warnings
+  strict: bool,
+  source: String,
   current_position: Option<Position>,
+  diagnostics: Vec<Diagnostic>,
+  anchors: HashSet<String>,
 }
 
 type Result<T> = std::result::Result<T, ParseError>;
 
 impl NahidaParser {
+  pub fn strict(mut self, strict: bool) -> Self {
+    self.strict = strict;
+    self
+  }
+
+  /// parse the whole script, collecting every error instead of stopping at the first one
+  pub fn parse(&mut self, text: &str) -> ParseOutput {
+    self.source = text.to_string();
+    self.anchors.clear();
+
+    let story = match markdown::to_mdast(text, &markdown::ParseOptions::default()) {
+      Ok(Node::Root(root)) => self.parse_root(&root),
+      Ok(_) => unreachable!(),
+      Err(x) => {
+        let error = self.throw(ParseErrorType::MdastError(x));
+        self.report(error);
+        Story { steps: Vec::new() }
+      }
+    };
+
+    ParseOutput {
+      story,
+      diagnostics: std::mem::take(&mut self.diagnostics),
+    }
+  }
+
+  /// parse the whole script, failing with the first error
   pub fn parse_text(&mut self, text: &str) -> Result<Story> {
-    match markdown::to_mdast(text, &markdown::ParseOptions::default())
-      .map_err(|x| self.throw(ParseErrorType::MdastError(x)))?
+    let output = self.parse(text);
+
+    match output
+      .diagnostics
+      .into_iter()
+      .find(|diagnostic| diagnostic.severity == Severity::Error)
     {
-      Node::Root(root) => Ok(self.parse_root(&root)?),
-      _ => unreachable!(),
+      Some(diagnostic) => Err(diagnostic.error),
+      None => Ok(output.story),
     }
   }
 }
 
+/// the first word of a link with only text inside
+fn link_word(link: &Link) -> Option<&str> {
+  match &link.children[..] {
+    [Node::Text(Text { value, .. })] => Tokenizer::new(value).next(),
+    _ => None,
+  }
+}
+
+/// `[text](#rrggbb)` colors the text, unless the word makes it another kind of link
+fn link_color(link: &Link) -> Option<Color> {
+  match link_word(link) {
+    Some(word) if LINKS.contains(&word) => None,
+    _ => Color::hex(&link.url),
+  }
+}
+
 impl NahidaParser {
   fn throw(&self, ty: ParseErrorType) -> ParseError {
     ParseError {
@@ -62,22 +235,116 @@ impl NahidaParser {
     }
   }
 
-  fn parse_root(&mut self, root: &Root) -> Result<Story> {
+  fn report(&mut self, error: ParseError) {
+    self.diagnostics.push(Diagnostic {
+      severity: Severity::Error,
+      error,
+    });
+  }
+
+  /// report issues found by the tokenizer in the text starting at `offset` of the source
+  fn report_tokens(&mut self, offset: Option<usize>, issues: Vec<(ParseErrorType, Range<usize>)>) {
+    let severity = match self.strict {
+      true => Severity::Error,
+      false => Severity::Warning,
+    };
+
+    for (ty, range) in issues {
+      let position = match offset {
+        Some(offset) => Some(Position {
+          start: self.point(offset + range.start),
+          end: self.point(offset + range.end),
+        }),
+        None => self.current_position.clone(),
+      };
+
+      self.diagnostics.push(Diagnostic {
+        severity,
+        error: ParseError { ty, position },
+      });
+    }
+  }
+
+  fn point(&self, offset: usize) -> Point {
+    let before = &self.source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |x| x + 1);
+
+    Point {
+      line: before.matches('\n').count() + 1,
+      column: offset - line_start + 1,
+      offset,
+    }
+  }
+
+  /// identify the step by its source
+  fn step_id(&self, position: &Option<Position>, anchor: Option<String>) -> StepId {
+    let (source, line) = position
+      .as_ref()
+      .and_then(|x| {
+        let source = self.source.get(x.start.offset..x.end.offset)?;
+        Some((source, x.start.line))
+      })
+      .unwrap_or_default();
+
+    StepId::new(source, line, anchor)
+  }
+
+  /// keep the value, or report the error and go on
+  fn recover<T>(&mut self, result: Result<T>) -> Option<T> {
+    match result {
+      Ok(value) => Some(value),
+      Err(error) => {
+        self.report(error);
+        None
+      }
+    }
+  }
+
+  fn parse_root(&mut self, root: &Root) -> Story {
     self.current_position = root.position.clone();
 
-    let mut story = Story { steps: Vec::new() };
-    let mut name = None;
+    let steps = self.parse_blocks(&root.children, None);
 
-    for child in &root.children {
+    Story { steps }
+  }
+
+  /// parse a sequence of block nodes, shared by the root and the branches of a choice
+  fn parse_blocks(&mut self, children: &[Node], mut name: Option<String>) -> Vec<StoryStep> {
+    let mut steps = Vec::new();
+    let mut children = children.iter().peekable();
+
+    while let Some(child) = children.next() {
       match child {
-        Node::Heading(heading) => name = Some(self.parse_heading(heading)?),
+        Node::Heading(heading) => {
+          let result = self.parse_heading(heading);
+          name = self.recover(result);
+        }
         Node::ThematicBreak(_) => name = None,
-        Node::Paragraph(paragraph) => story.steps.push(self.parse_paragraph(paragraph, &name)?),
-        node => Err(self.throw(ParseErrorType::UnknownNode(format!("{node:?}"))))?,
+        Node::Paragraph(paragraph) => steps.push(self.parse_paragraph(paragraph, &name)),
+        Node::List(list) if !list.ordered => {
+          // an ordered list right after the choice holds its branches
+          let branches = match children.peek().copied() {
+            Some(Node::List(branches)) if branches.ordered => {
+              children.next();
+              Some(branches)
+            }
+            _ => None,
+          };
+          steps.push(self.parse_choice(list, branches, &name));
+        }
+        Node::List(list) => {
+          self.current_position = list.position.clone();
+          let error = self.throw(ParseErrorType::BranchWithoutChoice);
+          self.report(error);
+        }
+        node => {
+          let error = self.throw(ParseErrorType::UnknownNode(format!("{node:?}")));
+          self.report(error);
+        }
       }
     }
 
-    Ok(story)
+    steps
   }
 
   fn parse_heading(&mut self, heading: &Heading) -> Result<String> {
@@ -89,35 +356,163 @@ impl NahidaParser {
     }
   }
 
-  fn parse_paragraph(&mut self, paragraph: &Paragraph, name: &Option<String>) -> Result<StoryStep> {
+  fn parse_paragraph(&mut self, paragraph: &Paragraph, name: &Option<String>) -> StoryStep {
     self.current_position = paragraph.position.clone();
 
     let mut step = StoryStep {
+      id: StepId::default(),
       actions: Vec::new(),
     };
+    let mut anchor = None;
+    let mut voice = None;
+    let mut spans = Vec::new();
 
     for child in &paragraph.children {
       match child {
-        Node::Text(Text { value, .. }) => {
-          let action = StoryAction::Text {
-            name: name.clone(),
-            text: value.clone(),
-          };
-          step.actions.push(action);
+        Node::Text(_)
+        | Node::Emphasis(_)
+        | Node::Strong(_)
+        | Node::InlineCode(_)
+        | Node::Break(_) => {
+          let result = self.parse_inline(child, &TextStyle::default(), &mut spans);
+          self.recover(result);
         }
-        Node::Link(link) => {
-          let action = self.parse_link(link)?;
-          step.actions.push(action);
+        Node::Link(link) => match link_word(link) {
+          Some(ANCHOR) => {
+            let result = self.parse_anchor(link);
+            anchor = self.recover(result).or(anchor);
+          }
+          Some(PAUSE | SPEED | SFX) => {
+            let result = self.parse_marker(link);
+            spans.extend(self.recover(result));
+          }
+          _ if link_color(link).is_some() => {
+            let result = self.parse_inline(child, &TextStyle::default(), &mut spans);
+            self.recover(result);
+          }
+          _ => {
+            self.flush_text(&mut spans, name, &mut step);
+            let result = self.parse_link(link);
+            step.actions.extend(self.recover(result));
+          }
+        },
+        Node::Image(image) if Tokenizer::new(&image.alt).next() == Some(VOICE) => {
+          self.current_position = image.position.clone();
+
+          if voice.is_some() {
+            let error = self.throw(ParseErrorType::DuplicateVoice);
+            self.report(error);
+          } else {
+            voice = Some(PathBuf::from(&image.url));
+          }
         }
         Node::Image(image) => {
-          let action = self.parse_image(image)?;
-          step.actions.push(action);
+          self.flush_text(&mut spans, name, &mut step);
+          let result = self.parse_image(image);
+          step.actions.extend(self.recover(result));
+        }
+        node => {
+          let error = self.throw(ParseErrorType::UnknownNode(format!("{node:?}")));
+          self.report(error);
         }
-        _ => todo!(),
       }
     }
+    self.flush_text(&mut spans, name, &mut step);
 
-    Ok(step)
+    // bind the voice to the text of this paragraph
+    if let Some(url) = voice {
+      self.current_position = paragraph.position.clone();
+
+      match step.actions.iter_mut().find_map(|action| match action {
+        StoryAction::Text { voice, .. } => Some(voice),
+        _ => None,
+      }) {
+        Some(voice) => *voice = Some(url),
+        None => {
+          let error = self.throw(ParseErrorType::OrphanVoice);
+          self.report(error);
+        }
+      }
+    }
+
+    step.id = self.step_id(&paragraph.position, anchor);
+    step
+  }
+
+  /// parse inline text nodes into styled spans
+  fn parse_inline(
+    &mut self,
+    node: &Node,
+    style: &TextStyle,
+    spans: &mut Vec<TextSpan>,
+  ) -> Result<()> {
+    match node {
+      Node::Text(Text { value, .. }) => spans.push(TextSpan::Text {
+        text: value.clone(),
+        style: style.clone(),
+      }),
+      Node::InlineCode(InlineCode { value, .. }) => spans.push(TextSpan::Text {
+        text: value.clone(),
+        style: TextStyle {
+          code: true,
+          ..style.clone()
+        },
+      }),
+      Node::Break(_) => spans.push(TextSpan::Break),
+      Node::Emphasis(Emphasis { children, .. }) => {
+        let style = TextStyle {
+          italic: true,
+          ..style.clone()
+        };
+        for child in children {
+          self.parse_inline(child, &style, spans)?;
+        }
+      }
+      Node::Strong(Strong { children, .. }) => {
+        let style = TextStyle {
+          bold: true,
+          ..style.clone()
+        };
+        for child in children {
+          self.parse_inline(child, &style, spans)?;
+        }
+      }
+      Node::Link(link) if link_color(link).is_some() => {
+        let style = TextStyle {
+          color: link_color(link),
+          ..style.clone()
+        };
+        for child in &link.children {
+          self.parse_inline(child, &style, spans)?;
+        }
+      }
+      node => Err(self.throw(ParseErrorType::UnknownNode(format!("{node:?}"))))?,
+    }
+
+    Ok(())
+  }
+
+  /// turn the collected spans into a text action
+  fn flush_text(&mut self, spans: &mut Vec<TextSpan>, name: &Option<String>, step: &mut StoryStep) {
+    trim_spans(spans);
+
+    if spans.iter().any(|x| matches!(x, TextSpan::Text { .. })) {
+      step.actions.push(StoryAction::Text {
+        name: name.clone(),
+        text: RichText::new(std::mem::take(spans)),
+        voice: None,
+      });
+      return;
+    }
+
+    // markers without any text to type act on their own
+    for span in spans.drain(..) {
+      match span {
+        TextSpan::Pause(time) => step.actions.push(StoryAction::Wait { time }),
+        TextSpan::Sfx(url) => step.actions.push(StoryAction::Sfx { url }),
+        _ => {}
+      }
+    }
   }
 
   fn parse_link(&mut self, link: &Link) -> Result<StoryAction> {
@@ -130,15 +525,15 @@ impl NahidaParser {
     let mut text = Tokenizer::new(&text);
 
     match text.next() {
-      Some("goto") => Ok(StoryAction::Navigate {
+      Some(GOTO) => Ok(StoryAction::Navigate {
         url: PathBuf::from(&link.url),
         ret: false,
       }),
-      Some("end") => Ok(StoryAction::Navigate {
+      Some(END) => Ok(StoryAction::Navigate {
         url: PathBuf::from(&link.url),
         ret: true,
       }),
-      Some("wait") => Ok(StoryAction::Wait {
+      Some(WAIT) => Ok(StoryAction::Wait {
         time: Duration::from_millis(
           link
             .url
@@ -147,26 +542,71 @@ impl NahidaParser {
             .map_err(|_| self.throw(ParseErrorType::InvalidWaitTime(link.url.clone())))?,
         ),
       }),
+      Some(word) => Err(self.throw(ParseErrorType::UnknownLink(word.to_string()))),
+      None => Err(self.throw(ParseErrorType::InvalidLink)),
+    }
+  }
+
+  /// parse the inline markers timing the typing of the text
+  fn parse_marker(&mut self, link: &Link) -> Result<TextSpan> {
+    self.current_position = link.position.clone();
+
+    let value = link.url.trim_start_matches('#');
+    match link_word(link) {
+      Some(PAUSE) => match value.parse() {
+        Ok(time) => Ok(TextSpan::Pause(Duration::from_millis(time))),
+        Err(_) => Err(self.throw(ParseErrorType::InvalidPauseTime(link.url.clone()))),
+      },
+      Some(SPEED) => match value.parse::<f32>() {
+        Ok(speed) if speed > 0.0 => Ok(TextSpan::Speed(speed)),
+        _ => Err(self.throw(ParseErrorType::InvalidSpeed(link.url.clone()))),
+      },
+      Some(SFX) => Ok(TextSpan::Sfx(PathBuf::from(&link.url))),
       _ => Err(self.throw(ParseErrorType::InvalidLink)),
     }
   }
 
+  /// name the step with `[anchor](#name)`, so that saves can find it after edits
+  fn parse_anchor(&mut self, link: &Link) -> Result<String> {
+    self.current_position = link.position.clone();
+
+    let name = match link.url.strip_prefix('#') {
+      Some(name) if !name.is_empty() => name.to_string(),
+      _ => Err(self.throw(ParseErrorType::InvalidAnchor(link.url.clone())))?,
+    };
+
+    match self.anchors.insert(name.clone()) {
+      true => Ok(name),
+      false => Err(self.throw(ParseErrorType::DuplicateAnchor(name))),
+    }
+  }
+
   fn parse_image(&mut self, image: &Image) -> Result<StoryAction> {
     self.current_position = image.position.clone();
 
     let mut alt = Tokenizer::new(&image.alt);
-    let title = image.title.clone().unwrap_or_default();
-    let mut title = Tokenizer::new(&title);
+    let title_text = image.title.clone().unwrap_or_default();
+    let mut title = Tokenizer::new(&title_text);
     let url = PathBuf::from(&image.url);
 
-    match alt.next() {
-      Some("bg") => Ok(StoryAction::Bg {
+    // find out where the alt and title texts are in the source
+    let raw = image.position.as_ref().and_then(|x| {
+      Some((
+        x.start.offset,
+        self.source.get(x.start.offset..x.end.offset)?,
+      ))
+    });
+    let alt_offset = raw.and_then(|(start, raw)| Some(start + 2 + raw.get(2..)?.find(&image.alt)?));
+    let title_offset = raw.and_then(|(start, raw)| Some(start + raw.rfind(title_text.as_str())?));
+
+    let action = match alt.next() {
+      Some(BG) => Ok(StoryAction::Bg {
         url,
         transition: alt.parse_transition(),
         location: title.parse_location(),
         animation: title.parse_animation(),
       }),
-      Some("fig") => Ok(StoryAction::Fig {
+      Some(FIG) => Ok(StoryAction::Fig {
         url,
         removal: alt.parse_remove(),
         transition: alt.parse_transition(),
@@ -176,9 +616,81 @@ impl NahidaParser {
         location: title.parse_location(),
         animation: title.parse_animation(),
       }),
-      Some("bgm") => Ok(StoryAction::Bgm { url }),
-      Some("sfx") => Ok(StoryAction::Sfx { url }),
-      _ => Err(self.throw(ParseErrorType::InvalidImage)),
+      Some(BGM) => Ok(StoryAction::Bgm { url }),
+      Some(SFX) => Ok(StoryAction::Sfx { url }),
+      Some(word) => Err(self.throw(ParseErrorType::UnknownImage(word.to_string()))),
+      None => Err(self.throw(ParseErrorType::InvalidImage)),
+    };
+
+    if action.is_ok() {
+      alt.finish();
+      title.finish();
+      self.report_tokens(alt_offset, alt.take_issues());
+      self.report_tokens(title_offset, title.take_issues());
+    }
+
+    action
+  }
+
+  fn parse_choice(
+    &mut self,
+    list: &List,
+    branches: Option<&List>,
+    name: &Option<String>,
+  ) -> StoryStep {
+    self.current_position = list.position.clone();
+
+    let mut options = Vec::new();
+
+    for child in &list.children {
+      let result = match child {
+        Node::ListItem(item) => self.parse_choice_option(item),
+        _ => Err(self.throw(ParseErrorType::InvalidChoice)),
+      };
+      // keep the broken option so that the branches still line up
+      options.push(ChoiceOption {
+        text: self.recover(result).unwrap_or_default(),
+        steps: Vec::new(),
+      });
+    }
+
+    if let Some(branches) = branches {
+      self.current_position = branches.position.clone();
+
+      if branches.children.len() != options.len() {
+        let error = self.throw(ParseErrorType::ChoiceBranchMismatch {
+          options: options.len(),
+          branches: branches.children.len(),
+        });
+        self.report(error);
+      }
+
+      for (option, child) in options.iter_mut().zip(&branches.children) {
+        match child {
+          Node::ListItem(item) => option.steps = self.parse_blocks(&item.children, name.clone()),
+          _ => {
+            let error = self.throw(ParseErrorType::InvalidChoice);
+            self.report(error);
+          }
+        }
+      }
+    }
+
+    StoryStep {
+      id: self.step_id(&list.position, None),
+      actions: vec![StoryAction::Choice { options }],
+    }
+  }
+
+  fn parse_choice_option(&mut self, item: &ListItem) -> Result<String> {
+    self.current_position = item.position.clone();
+
+    match &item.children[..] {
+      [Node::Paragraph(Paragraph { children, .. })] => match &children[..] {
+        [Node::Text(Text { value, .. })] => Ok(value.clone()),
+        _ => Err(self.throw(ParseErrorType::InvalidChoice)),
+      },
+      _ => Err(self.throw(ParseErrorType::InvalidChoice)),
     }
   }
 }
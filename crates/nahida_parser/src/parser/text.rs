@@ -0,0 +1,97 @@
+//! This module handles the inline text of paragraphs
+//!
+
+use nahida_core::text::TextSpan;
+
+/// remove whitespaces and line breaks around the spans, markers are kept
+pub fn trim_spans(spans: &mut Vec<TextSpan>) {
+  let mut index = 0;
+  while let Some(span) = spans.get_mut(index) {
+    match span {
+      TextSpan::Text { text, .. } => {
+        let trimmed = text.trim_start();
+        if !trimmed.is_empty() {
+          *text = trimmed.to_string();
+          break;
+        }
+      }
+      TextSpan::Break => {}
+      _ => {
+        index += 1;
+        continue;
+      }
+    }
+    spans.remove(index);
+  }
+
+  let mut index = spans.len();
+  while let Some(span) = index.checked_sub(1).and_then(|x| spans.get_mut(x)) {
+    match span {
+      TextSpan::Text { text, .. } => {
+        let trimmed = text.trim_end();
+        if !trimmed.is_empty() {
+          *text = trimmed.to_string();
+          break;
+        }
+      }
+      TextSpan::Break => {}
+      _ => {
+        index -= 1;
+        continue;
+      }
+    }
+    index -= 1;
+    spans.remove(index);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nahida_core::text::TextSpan;
+
+  use super::trim_spans;
+
+  #[test]
+  fn test_trim_spans() {
+    let mut spans = vec![
+      TextSpan::plain("\n"),
+      TextSpan::Break,
+      TextSpan::plain("\n「你好」 "),
+      TextSpan::Break,
+      TextSpan::plain(" 「呀」\n"),
+      TextSpan::Break,
+    ];
+    trim_spans(&mut spans);
+
+    assert_eq!(
+      spans,
+      vec![
+        TextSpan::plain("「你好」 "),
+        TextSpan::Break,
+        TextSpan::plain(" 「呀」"),
+      ]
+    );
+
+    let mut spans = vec![
+      TextSpan::Speed(2.0),
+      TextSpan::plain(" 「你好」\n"),
+      TextSpan::Sfx("./ding.mp3".into()),
+      TextSpan::Break,
+    ];
+    trim_spans(&mut spans);
+
+    assert_eq!(
+      spans,
+      vec![
+        TextSpan::Speed(2.0),
+        TextSpan::plain("「你好」"),
+        TextSpan::Sfx("./ding.mp3".into()),
+      ]
+    );
+
+    let mut spans = vec![TextSpan::plain("  "), TextSpan::Break];
+    trim_spans(&mut spans);
+
+    assert_eq!(spans, vec![]);
+  }
+}
@@ -0,0 +1,62 @@
+use nahida_core::story::{StepId, StoryAction};
+
+use crate::{
+  parse,
+  parser::{NahidaParser, ParseErrorType},
+  steps,
+  tests::clear_ids,
+};
+
+#[test]
+fn test_anchor() {
+  let story = NahidaParser::default()
+    .parse_text(include_str!("anchor.md"))
+    .unwrap();
+  let ids = story.steps.iter().map(|x| &x.id).collect::<Vec<_>>();
+
+  assert_eq!(ids[0].anchor, Some("start".to_string()));
+  assert_eq!(
+    ids.iter().map(|x| x.line).collect::<Vec<_>>(),
+    vec![3, 6, 8]
+  );
+  assert_eq!(ids[1], &StepId::new("「那就出发吧」", 6, None));
+
+  // the saved steps are still found after the script is edited
+  let edited = include_str!("anchor.md")
+    .replace(
+      "[anchor](#start)\n「要一起",
+      "[anchor](#start)\n「想不想一起",
+    )
+    .replace("「那就出发吧」", "「好」\n\n「那就出发吧」");
+  let edited = NahidaParser::default().parse_text(&edited).unwrap();
+
+  let located = ids
+    .iter()
+    .map(|id| id.locate(&edited.steps))
+    .collect::<Vec<_>>();
+  assert_eq!(located, vec![Some(0), Some(2), Some(3)]);
+
+  // the anchor is not a part of the text
+  assert_eq!(
+    clear_ids(story).steps[0],
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: "「要一起去须弥城吗？」".into(),
+      voice: None
+    }]
+  );
+}
+
+#[test]
+fn test_anchor_error() {
+  assert_eq!(
+    parse!("[anchor](start)\n").unwrap_err().ty(),
+    &ParseErrorType::InvalidAnchor("start".into())
+  );
+  assert_eq!(
+    parse!("[anchor](#a)\n「一」\n\n[anchor](#a)\n「二」\n")
+      .unwrap_err()
+      .ty(),
+    &ParseErrorType::DuplicateAnchor("a".into())
+  );
+}
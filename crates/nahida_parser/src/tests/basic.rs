@@ -7,23 +7,28 @@ fn test_basic() {
   let story = story![
     steps![StoryAction::Text {
       name: Some("纳西妲".to_string()),
-      text: "「你好呀」".to_string()
+      text: "「你好呀」".into(),
+      voice: None
     }],
     steps![StoryAction::Text {
       name: Some("我".to_string()),
-      text: "「……」".to_string()
+      text: "「……」".into(),
+      voice: None
     }],
     steps![StoryAction::Text {
       name: Some("我".to_string()),
-      text: "「……你好」".to_string()
+      text: "「……你好」".into(),
+      voice: None
     }],
     steps![StoryAction::Text {
       name: None,
-      text: "突然有个羽毛球上前向我搭话，我莫名感觉到有些慌乱".to_string()
+      text: "突然有个羽毛球上前向我搭话，我莫名感觉到有些慌乱".into(),
+      voice: None
     }],
     steps![StoryAction::Text {
       name: None,
-      text: "定睛一看，才发现是个少女".to_string()
+      text: "定睛一看，才发现是个少女".into(),
+      voice: None
     }],
   ];
 
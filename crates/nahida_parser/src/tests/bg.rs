@@ -2,7 +2,10 @@ use std::{path::PathBuf, time::Duration};
 
 use nahida_core::{
   easing::EasingFunction,
-  story::{Animation, AnimationType, StoryAction, Transition, TransitionType},
+  story::{
+    Animation, AnimationDirection, AnimationFill, AnimationIteration, AnimationType, StoryAction,
+    Transition, TransitionType,
+  },
   Location, Position, Size,
 };
 
@@ -56,6 +59,10 @@ fn test_background() {
         },
         time: Duration::from_secs(60),
         easing: EasingFunction::Linear,
+        delay: Duration::ZERO,
+        iteration: AnimationIteration::Count(1.0),
+        direction: AnimationDirection::Normal,
+        fill: AnimationFill::Forwards,
       }),
       location: Location {
         position: Position(0.7, 1.0),
@@ -74,6 +81,10 @@ fn test_background() {
         },
         time: Duration::from_secs(20),
         easing: EasingFunction::ease_in_out(),
+        delay: Duration::ZERO,
+        iteration: AnimationIteration::Count(1.0),
+        direction: AnimationDirection::Normal,
+        fill: AnimationFill::Forwards,
       }),
       location: Location {
         position: Position(0.7, 1.0),
@@ -85,7 +96,8 @@ fn test_background() {
       transition: Some(Transition {
         ty: TransitionType::FadeIn,
         time: Duration::from_secs(1),
-        easing: EasingFunction::Linear
+        easing: EasingFunction::Linear,
+        shake: false,
       }),
       animation: None,
       location: Location::default()
@@ -95,10 +107,47 @@ fn test_background() {
       transition: Some(Transition {
         ty: TransitionType::FadeOut,
         time: Duration::from_secs(5),
-        easing: EasingFunction::StepStart
+        easing: EasingFunction::StepStart,
+        shake: false,
       }),
       animation: None,
       location: Location::default()
+    }],
+    steps![StoryAction::Bg {
+      url: PathBuf::from("./background.png"),
+      transition: None,
+      animation: Some(Animation {
+        ty: AnimationType::To {
+          location: Location {
+            position: Position(1.0, 0.5),
+            size: Size::default(),
+          }
+        },
+        time: Duration::from_secs(30),
+        easing: EasingFunction::Linear,
+        delay: Duration::from_secs(2),
+        iteration: AnimationIteration::Infinite,
+        direction: AnimationDirection::Alternate,
+        fill: AnimationFill::Forwards,
+      }),
+      location: Location {
+        position: Position(0.0, 0.5),
+        size: Size::default(),
+      },
+    }],
+    steps![StoryAction::Bg {
+      url: PathBuf::from("./background.png"),
+      transition: Some(Transition {
+        ty: TransitionType::Rule {
+          mask: PathBuf::from("./rules/swirl.png"),
+          softness: 0.2,
+        },
+        time: Duration::from_secs(2),
+        easing: EasingFunction::ease_in(),
+        shake: false,
+      }),
+      animation: None,
+      location: Location::default(),
     }]
   ];
 
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use nahida_core::story::{ChoiceOption, StoryAction};
+
+use crate::{parse, steps, story};
+
+#[test]
+fn test_choice() {
+  let story = story![
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: "「要一起去须弥城吗？」".into(),
+      voice: None
+    }],
+    steps![StoryAction::Choice {
+      options: vec![
+        ChoiceOption {
+          text: "好啊".to_string(),
+          steps: vec![
+            steps![StoryAction::Text {
+              name: Some("纳西妲".to_string()),
+              text: "「那就出发吧」".into(),
+              voice: None
+            }],
+            steps![StoryAction::Navigate {
+              url: PathBuf::from("./happy_end.md"),
+              ret: false
+            }],
+          ]
+        },
+        ChoiceOption {
+          text: "还是算了".to_string(),
+          steps: vec![steps![StoryAction::Navigate {
+            url: PathBuf::from("./bad_end.md"),
+            ret: false
+          }]]
+        },
+      ]
+    }],
+    steps![StoryAction::Choice {
+      options: vec![ChoiceOption {
+        text: "再想想".to_string(),
+        steps: vec![]
+      }]
+    }],
+  ];
+
+  assert_eq!(parse!(include_str!("choice.md")), Ok(story));
+}
+
+#[test]
+fn test_choice_mismatch() {
+  let text = "- 好啊\n- 还是算了\n\n1. [goto](./happy_end.md)\n";
+
+  assert!(parse!(text).is_err());
+  assert!(parse!("1. [goto](./happy_end.md)\n").is_err());
+}
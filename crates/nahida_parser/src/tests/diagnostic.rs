@@ -0,0 +1,66 @@
+use nahida_core::story::StoryAction;
+
+use crate::{
+  parse_story,
+  parser::{NahidaParser, ParseErrorType, Severity},
+  steps,
+  tests::clear_ids,
+};
+
+#[test]
+fn test_diagnostic() {
+  let output = NahidaParser::default().parse(include_str!("diagnostic.md"));
+
+  let diagnostics = output
+    .diagnostics
+    .iter()
+    .map(|diagnostic| (diagnostic.severity, diagnostic.error.ty()))
+    .collect::<Vec<_>>();
+
+  assert_eq!(
+    diagnostics,
+    vec![
+      (Severity::Error, &ParseErrorType::UnknownImage("bgg".into())),
+      (Severity::Error, &ParseErrorType::UnknownLink("gogo".into())),
+      (Severity::Warning, &ParseErrorType::MissingSizeWords),
+      (Severity::Error, &ParseErrorType::OrphanVoice),
+    ]
+  );
+
+  let lines = output
+    .diagnostics
+    .iter()
+    .map(|diagnostic| diagnostic.error.position().map(|x| x.start.line))
+    .collect::<Vec<_>>();
+
+  assert_eq!(lines, vec![Some(5), Some(8), Some(10), Some(12)]);
+
+  // the warning is not counted as an error
+  assert_eq!(output.errors().count(), 3);
+  let error = parse_story(include_str!("diagnostic.md")).unwrap_err();
+  assert_eq!(error.to_string(), "failed to parse: 3 errors");
+
+  // everything else is still parsed
+  let story = clear_ids(output.story);
+  let steps = &story.steps;
+
+  assert_eq!(steps.len(), 6);
+  assert_eq!(
+    steps[1],
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: "「背景没有换掉」".into(),
+      voice: None
+    }]
+  );
+  assert_eq!(steps[2], steps![]);
+  assert!(matches!(steps[3].actions[..], [StoryAction::Bg { .. }]));
+  assert_eq!(
+    steps[5],
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: "「最后一句」".into(),
+      voice: None
+    }]
+  );
+}
@@ -2,7 +2,10 @@ use std::{path::PathBuf, time::Duration};
 
 use nahida_core::{
   easing::EasingFunction,
-  story::{Animation, AnimationType, StoryAction, Transition, TransitionType},
+  story::{
+    Animation, AnimationDirection, AnimationFill, AnimationIteration, AnimationType, StoryAction,
+    Transition, TransitionType,
+  },
   Location, Position, Size,
 };
 
@@ -25,7 +28,8 @@ fn test_figure() {
       transition: Some(Transition {
         ty: TransitionType::FadeIn,
         time: Duration::from_secs(4),
-        easing: EasingFunction::ease_out()
+        easing: EasingFunction::ease_out(),
+        shake: false,
       }),
       animation: Some(Animation {
         ty: AnimationType::To {
@@ -35,7 +39,11 @@ fn test_figure() {
           }
         },
         time: Duration::from_secs(20),
-        easing: EasingFunction::ease_in_out()
+        easing: EasingFunction::ease_in_out(),
+        delay: Duration::ZERO,
+        iteration: AnimationIteration::Count(1.0),
+        direction: AnimationDirection::Normal,
+        fill: AnimationFill::Forwards,
       }),
       location: Location {
         position: Position(0.7, 1.0),
@@ -49,7 +57,8 @@ fn test_figure() {
       transition: Some(Transition {
         ty: TransitionType::ConicOut,
         time: Duration::from_secs(4),
-        easing: EasingFunction::Linear
+        easing: EasingFunction::Linear,
+        shake: false,
       }),
       animation: None,
       location: Location::default(),
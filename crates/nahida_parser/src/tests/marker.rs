@@ -0,0 +1,51 @@
+use std::{path::PathBuf, time::Duration};
+
+use nahida_core::{
+  story::StoryAction,
+  text::{RichText, TextSpan},
+};
+
+use crate::{parse, parser::ParseErrorType, steps, story};
+
+#[test]
+fn test_marker() {
+  let story = story![
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: RichText::new(vec![
+        TextSpan::plain("「这是……"),
+        TextSpan::Pause(Duration::from_millis(500)),
+        TextSpan::plain("什么？」"),
+      ]),
+      voice: None
+    }],
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: RichText::new(vec![
+        TextSpan::Speed(0.5),
+        TextSpan::plain("「慢慢来，"),
+        TextSpan::Sfx(PathBuf::from("./sfx/ding.mp3")),
+        TextSpan::plain("不要急」"),
+      ]),
+      voice: None
+    }],
+    // a marker without text plays by itself
+    steps![StoryAction::Sfx {
+      url: PathBuf::from("./sfx/open_letter.mp3")
+    }],
+  ];
+
+  assert_eq!(parse!(include_str!("marker.md")), Ok(story));
+}
+
+#[test]
+fn test_invalid_marker() {
+  let error = parse!("「这是……[pause](#soon)什么？」\n").unwrap_err();
+  assert_eq!(
+    error.ty(),
+    &ParseErrorType::InvalidPauseTime("#soon".to_string())
+  );
+
+  let error = parse!("[speed](#0)「慢慢来」\n").unwrap_err();
+  assert_eq!(error.ty(), &ParseErrorType::InvalidSpeed("#0".to_string()));
+}
@@ -1,6 +1,13 @@
+mod anchor;
 mod basic;
 mod bg;
+mod choice;
+mod diagnostic;
 mod fig;
+mod marker;
+mod strict;
+mod text;
+mod voice;
 
 #[macro_export]
 macro_rules! story {
@@ -15,16 +22,37 @@ macro_rules! story {
 #[macro_export]
 macro_rules! steps {
   () => (
-    nahida_core::story::StoryStep { actions: Vec::new() }
+    nahida_core::story::StoryStep { id: Default::default(), actions: Vec::new() }
   );
   ($($x:expr),+ $(,)?) => (
-    nahida_core::story::StoryStep { actions: vec![$($x),+] }
+    nahida_core::story::StoryStep { id: Default::default(), actions: vec![$($x),+] }
   );
 }
 
 #[macro_export]
 macro_rules! parse {
   ($text:expr) => {
-    $crate::parser::NahidaParser::default().parse_text($text)
+    $crate::parser::NahidaParser::default()
+      .parse_text($text)
+      .map($crate::tests::clear_ids)
   };
 }
+
+/// drop the step ids, which are tested on their own
+pub(crate) fn clear_ids(mut story: nahida_core::story::Story) -> nahida_core::story::Story {
+  fn clear(steps: &mut [nahida_core::story::StoryStep]) {
+    for step in steps {
+      step.id = Default::default();
+      for action in &mut step.actions {
+        if let nahida_core::story::StoryAction::Choice { options } = action {
+          for option in options {
+            clear(&mut option.steps);
+          }
+        }
+      }
+    }
+  }
+
+  clear(&mut story.steps);
+  story
+}
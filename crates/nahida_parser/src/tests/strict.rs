@@ -0,0 +1,52 @@
+use crate::parser::{NahidaParser, ParseErrorType, Severity};
+
+#[test]
+fn test_strict() {
+  let text = include_str!("strict.md");
+
+  let output = NahidaParser::default().strict(true).parse(text);
+  let diagnostics = output
+    .diagnostics
+    .iter()
+    .map(|diagnostic| {
+      let position = diagnostic.error.position().unwrap();
+      (
+        diagnostic.severity,
+        diagnostic.error.ty(),
+        &text[position.start.offset..position.end.offset],
+      )
+    })
+    .collect::<Vec<_>>();
+
+  assert_eq!(
+    diagnostics,
+    vec![
+      (
+        Severity::Error,
+        &ParseErrorType::UnknownToken("fade-inn".into()),
+        "fade-inn"
+      ),
+      (
+        Severity::Error,
+        &ParseErrorType::InvalidPosition("left 20% 20%".into()),
+        "left 20% 20%"
+      ),
+      (
+        Severity::Error,
+        &ParseErrorType::ConflictingTransition("fade-in".into(), "conic-in".into()),
+        "conic-in"
+      ),
+      (
+        Severity::Error,
+        &ParseErrorType::InvalidSize("30% 30% 30%".into()),
+        "30% 30% 30%"
+      ),
+    ]
+  );
+
+  // the same issues are only warnings by default
+  let output = NahidaParser::default().parse(text);
+
+  assert_eq!(output.diagnostics.len(), 4);
+  assert!(!output.has_errors());
+}
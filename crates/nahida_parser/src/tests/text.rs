@@ -0,0 +1,96 @@
+use nahida_core::{
+  story::StoryAction,
+  text::{RichText, TextSpan, TextStyle},
+  Color,
+};
+
+use crate::{
+  parse,
+  parser::{NahidaParser, LINKS},
+  steps, story,
+};
+
+#[test]
+fn test_text() {
+  let story = story![
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: RichText::new(vec![
+        TextSpan::plain("「这是"),
+        TextSpan::Text {
+          text: "很重要".to_string(),
+          style: TextStyle {
+            italic: true,
+            ..Default::default()
+          }
+        },
+        TextSpan::plain("的事情」"),
+      ]),
+      voice: None
+    }],
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: RichText::new(vec![
+        TextSpan::Text {
+          text: "（她在想什么呢？）".to_string(),
+          style: TextStyle {
+            bold: true,
+            ..Default::default()
+          }
+        },
+        TextSpan::Break,
+        TextSpan::Text {
+          text: "虚空终端".to_string(),
+          style: TextStyle {
+            code: true,
+            ..Default::default()
+          }
+        },
+        TextSpan::plain("里面"),
+        TextSpan::Text {
+          text: "什么都有".to_string(),
+          style: TextStyle {
+            bold: true,
+            italic: true,
+            ..Default::default()
+          }
+        },
+      ]),
+      voice: None
+    }],
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: RichText::new(vec![
+        TextSpan::plain("「这是"),
+        TextSpan::Text {
+          text: "红色".to_string(),
+          style: TextStyle {
+            italic: true,
+            color: Some(Color::rgba(1.0, 0.0, 0.0, 1.0)),
+            ..Default::default()
+          }
+        },
+        TextSpan::plain("的字」"),
+      ]),
+      voice: None
+    }],
+  ];
+
+  assert_eq!(parse!(include_str!("text.md")), Ok(story));
+}
+
+#[test]
+fn test_link_words_not_colored() {
+  let colored =
+    |span: &TextSpan| matches!(span, TextSpan::Text { style, .. } if style.color.is_some());
+
+  // `#abc` is a color as well, but the words make other kinds of links
+  for word in LINKS {
+    let output = NahidaParser::default().parse(&format!("「这是[{word}](#abc)」"));
+    for action in output.story.steps.iter().flat_map(|x| &x.actions) {
+      if let StoryAction::Text { text, .. } = action {
+        assert!(!text.spans.iter().any(colored), "{word}");
+      }
+    }
+  }
+}
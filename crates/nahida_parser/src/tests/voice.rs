@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use nahida_core::story::StoryAction;
+
+use crate::{parse, steps, story};
+
+#[test]
+fn test_voice() {
+  let story = story![
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: "「你说的对，但是原神是……」".into(),
+      voice: Some(PathBuf::from("./vocal/nahida-1.mp3"))
+    }],
+    steps![StoryAction::Text {
+      name: Some("纳西妲".to_string()),
+      text: "「后面忘了」".into(),
+      voice: Some(PathBuf::from("./vocal/nahida-2.mp3"))
+    }],
+  ];
+
+  assert_eq!(parse!(include_str!("voice.md")), Ok(story));
+}
+
+#[test]
+fn test_voice_mismatch() {
+  assert!(parse!("![v](./vocal/nahida-1.mp3)\n").is_err());
+  assert!(
+    parse!("![v](./vocal/nahida-1.mp3)\n![v](./vocal/nahida-2.mp3)\n「后面忘了」\n").is_err()
+  );
+}
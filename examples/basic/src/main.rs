@@ -1,10 +1,15 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
-use nahida_bevy::{NahidaEntryPoint, NahidaPlugin};
+use nahida_bevy::{NahidaAppName, NahidaEntryPoint, NahidaMenuConfig, NahidaPlugin};
 
 fn main() {
   App::new()
     .insert_resource(NahidaEntryPoint("story.md".to_string()))
+    .insert_resource(NahidaAppName("nahida-basic".to_string()))
+    .insert_resource(NahidaMenuConfig {
+      title: Some("纳西妲".to_string()),
+      ..Default::default()
+    })
     .add_plugin(NahidaPlugin)
     .add_plugin(WorldInspectorPlugin::new())
     .run();